@@ -1,3 +1,5 @@
+local ladder = require("ladder")
+
 Description = [[
 If you choose to be a Full Caster when choosing your class, you get some benefits to casting stronger spells more often but your access to weapons, armors, and health will not be as strong as the others. You won’t be deadly with a blade but you will be a wicked spell slinger.
 
@@ -23,23 +25,15 @@ function Health(class_level)
 end
 
 function SpellLevel(class_level)
-    if class_level >= 1 and class_level <= 2 then
-        return 1 -- Level 1 = 1st level spells
-    elseif class_level >= 3 and class_level <= 4 then
-        return 2 -- Level 3 = 2nd level spells
-    elseif class_level >= 5 and class_level <= 6 then
-        return 3 -- Level 5 = 3rd level spells
-    elseif class_level >= 7 and class_level <= 8 then
-        return 4 -- Level 7 = 4th level spells
-    elseif class_level >= 9 and class_level <= 10 then
-        return 5 -- Level 9 = 5th level spells
-    elseif class_level >= 11 and class_level <= 12 then
-        return 6 -- Level 11 = 6th level spells
-    elseif class_level >= 13 and class_level <= 14 then
-        return 7 -- Level 13 = 7th level spells
-    elseif class_level >= 15 and class_level <= 16 then
-        return 8 -- Level 15 = 8th level spells
-    elseif class_level >= 17 then
-        return 9 -- Level 17+ = 9th level spells
-    end
+    return ladder.highest({
+        [1] = 1, -- Level 1 = 1st level spells
+        [3] = 2, -- Level 3 = 2nd level spells
+        [5] = 3, -- Level 5 = 3rd level spells
+        [7] = 4, -- Level 7 = 4th level spells
+        [9] = 5, -- Level 9 = 5th level spells
+        [11] = 6, -- Level 11 = 6th level spells
+        [13] = 7, -- Level 13 = 7th level spells
+        [15] = 8, -- Level 15 = 8th level spells
+        [17] = 9, -- Level 17+ = 9th level spells
+    }, class_level)
 end
@@ -1,3 +1,5 @@
+local ladder = require("ladder")
+
 Description = [[
 If you choose to be a Full Martial when choosing your class, you get some huge benefits to weapons, armor, and health but you lose your access to magic. Imagine a cybernetic samurai or an ex-soldier sniper.
 
@@ -20,9 +22,8 @@ function ArmorRating(class_level)
 end
 
 function Skills(class_level)
-    local skill_table = { "Weapon Mastery", "Weapon Mastery" }
-    if class_level >= 5 then
-        table.insert(skill_table, "Extra Attack")
-    end
-    return skill_table
+    return ladder.unlocked({
+        [1] = { "Weapon Mastery", "Weapon Mastery" },
+        [5] = { "Extra Attack" },
+    }, class_level)
 end
@@ -1,3 +1,5 @@
+local ladder = require("ladder")
+
 Description = [[
 If you choose to be a Half Caster when choosing your class, you get about half the benefits to armors, weapons, and health and about half the access to magic as a Full Caster. You will only gain spells up to the spell level of 5 and you will have roughly half the spell slots of a Full Caster. This is a perfect balance between a Full Martial and a Full Caster character.
 
@@ -25,25 +27,18 @@ function ArmorRating(class_level)
 end
 
 function SpellLevel(class_level)
-    if class_level >= 0 and class_level <= 1 then
-        return 0 -- Level 0-1 = no spells
-    elseif class_level >= 2 and class_level <= 4 then
-        return 1 -- Level 2-4 = 1st level spells
-    elseif class_level >= 5 and class_level <= 8 then
-        return 2 -- Level 5-8 = 2nd level spells
-    elseif class_level >= 9 and class_level <= 12 then
-        return 3 -- Level 9-12 = 3rd level spells
-    elseif class_level >= 13 and class_level <= 16 then
-        return 4 -- Level 13-16 = 4th level spells
-    elseif class_level >= 17 then
-        return 5 -- Level 17+ = 5th level spells
-    end
+    return ladder.highest({
+        [2] = 1, -- Level 2-4 = 1st level spells
+        [5] = 2, -- Level 5-8 = 2nd level spells
+        [9] = 3, -- Level 9-12 = 3rd level spells
+        [13] = 4, -- Level 13-16 = 4th level spells
+        [17] = 5, -- Level 17+ = 5th level spells
+    }, class_level)
 end
 
 function Skills(class_level)
-    local skill_table = { "Weapon Mastery" }
-    if class_level >= 5 then
-        table.insert(skill_table, "Extra Attack")
-    end
-    return skill_table
+    return ladder.unlocked({
+        [1] = { "Weapon Mastery" },
+        [5] = { "Extra Attack" },
+    }, class_level)
 end
@@ -0,0 +1,19 @@
+Title = "Cybernetic TRPG"
+
+Version = "0.1.0"
+
+Authors = { "computing-fun" }
+
+License = "Apache-2.0"
+
+Description = [[
+The base game. Every class and balance a table needs to start playing, and the rules every homebrew book builds on.
+]]
+
+SystemVersion = "0.1.0"
+
+Rules = {
+    MaxLevel = 20,
+    SavingThrows = "FirstClass",
+    SpellLevels = "Highest",
+}
@@ -0,0 +1,27 @@
+-- Helpers for sheets whose values grow with the class level, use with
+-- local ladder = require("ladder")
+local ladder = {}
+
+-- Every name unlocked at or below `class_level`, lowest level first.
+-- `steps` maps a class level to the names it unlocks: { [2] = { "Extra Attack" } }
+function ladder.unlocked(steps, class_level)
+    local unlocked = {}
+    for level = 1, class_level do
+        for _, name in ipairs(steps[level] or {}) do
+            table.insert(unlocked, name)
+        end
+    end
+    return unlocked
+end
+
+-- The value of the highest step at or below `class_level`, 0 before the first one.
+-- `steps` maps a class level to the value from that level on: { [1] = 1, [3] = 2 }
+function ladder.highest(steps, class_level)
+    local value = 0
+    for level = 1, class_level do
+        value = steps[level] or value
+    end
+    return value
+end
+
+return ladder
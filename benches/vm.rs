@@ -0,0 +1,83 @@
+//! Compares one VM per book against the one VM per sheet there used to be.
+//! Run with `cargo bench --bench vm`.
+
+use std::time::{Duration, Instant};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/sheet/mod.rs"]
+mod sheet;
+
+use sheet::{limits::Limits, vm::Src, vm::Vm};
+
+const SHEETS: usize = 1000;
+const RUNS: u32 = 10;
+
+fn generated_sheet(number: usize) -> String {
+    format!(
+        "Description = \"Generated skill number {0}.\"\n\
+         Tags = {{ \"Generated\", \"Skill {0}\" }}\n\
+         function Skills(class_level)\n    \
+             local skills = {{}}\n    \
+             for level = 1, class_level do\n        \
+                 table.insert(skills, \"Skill \" .. level)\n    \
+             end\n    \
+             return skills\n\
+         end\n",
+        number
+    )
+}
+
+/// Loads every source into one VM, returning how long it took and the bytes it holds.
+fn vm_per_book(sources: &[String]) -> (Duration, usize) {
+    let start = Instant::now();
+    let vm = Vm::new(Limits::default()).unwrap();
+    let sheets: Vec<Src> = sources
+        .iter()
+        .map(|source| {
+            vm.load("generated", source.as_str(), |_, _| Ok(()))
+                .unwrap()
+        })
+        .collect();
+    let elapsed = start.elapsed();
+    assert_eq!(sheets.len(), sources.len());
+    (elapsed, vm.lua().used_memory())
+}
+
+/// Loads every source into a VM of its own, returning how long it took and the bytes
+/// they hold together.
+fn vm_per_sheet(sources: &[String]) -> (Duration, usize) {
+    let start = Instant::now();
+    let sheets: Vec<Src> = sources
+        .iter()
+        .map(|source| Src::load("generated", source.as_str()).unwrap())
+        .collect();
+    let elapsed = start.elapsed();
+    (
+        elapsed,
+        sheets.iter().map(|src| src.src().used_memory()).sum(),
+    )
+}
+
+/// Runs `load` `RUNS` times, reporting the fastest time and the memory of the last run.
+fn report(name: &str, sources: &[String], load: fn(&[String]) -> (Duration, usize)) {
+    let mut fastest = Duration::MAX;
+    let mut memory = 0;
+    for _ in 0..RUNS {
+        let (elapsed, used) = load(sources);
+        fastest = fastest.min(elapsed);
+        memory = used;
+    }
+    println!(
+        "{:<17} {:>8.1?} {:>8} KiB used_memory",
+        name,
+        fastest,
+        memory / 1024
+    );
+}
+
+fn main() {
+    let sources: Vec<String> = (0..SHEETS).map(generated_sheet).collect();
+    println!("{} sheets, fastest of {} runs", SHEETS, RUNS);
+    report("one VM per book:", &sources, vm_per_book);
+    report("one VM per sheet:", &sources, vm_per_sheet);
+}
@@ -1,2 +1,2 @@
 #!/bin/bash
-zip -r base_game.zip base_game && cargo run -- base_game.zip
\ No newline at end of file
+rm -f base_game.zip && (cd base_game && zip -r ../base_game.zip .) && cargo run -- base_game
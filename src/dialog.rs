@@ -53,6 +53,34 @@ pub fn create_book() -> Option<PathBuf> {
         })
 }
 
+const CHARACTER_EXTENSION: &str = "character.json";
+const CHARACTER_FILTER_NAME: &str = "Character file";
+const CHARACTER_FILTER_EXTENSIONS: &[&str; 1] = &[CHARACTER_EXTENSION];
+
+pub fn character_file() -> FileDialog {
+    FileDialog::new().add_filter(CHARACTER_FILTER_NAME, CHARACTER_FILTER_EXTENSIONS)
+}
+
+pub fn open_character() -> Option<PathBuf> {
+    character_file()
+        .set_title("Which character do you want to play?")
+        .pick_file()
+}
+
+pub fn save_character(name: &str) -> Option<PathBuf> {
+    character_file()
+        .set_title("Where should we keep this character?")
+        .set_file_name(format!("{}.{}", name, CHARACTER_EXTENSION))
+        .set_can_create_directories(true)
+        .save_file()
+        .map(|mut p| {
+            if !p.to_string_lossy().ends_with(CHARACTER_EXTENSION) {
+                p.set_extension(CHARACTER_EXTENSION);
+            }
+            p
+        })
+}
+
 pub enum OpenOrCreateBook {
     Open(PathBuf),
     Create(PathBuf),
@@ -81,5 +109,5 @@ pub fn open_or_create_book() -> Option<OpenOrCreateBook> {
             return Some(OpenOrCreateBook::Create(create_book()?));
         }
     };
-    return None;
+    None
 }
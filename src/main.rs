@@ -1,18 +1,21 @@
 #![allow(dead_code)]
 
-use std::{path::PathBuf, process::ExitCode, rc::Rc};
+use std::{cell::RefCell, path::PathBuf, process::ExitCode, rc::Rc};
 
-use sheet::book::{self, Book, BookMakerError};
+use sheet::{
+    book::{self, Book, BookMakerError},
+    shelf::Shelf,
+};
 
 mod dialog;
 mod sheet;
 mod view;
 
 fn main() -> ExitCode {
-    let book = Rc::new({
-        match get_book() {
-            Some(book_result) => match book_result {
-                Ok(book) => book,
+    let shelf = Rc::new(RefCell::new({
+        match get_books() {
+            Some(books_result) => match books_result {
+                Ok(books) => Shelf::new(books),
                 Err(err) => {
                     dialog::error(err);
                     return ExitCode::FAILURE;
@@ -20,36 +23,42 @@ fn main() -> ExitCode {
             },
             None => return ExitCode::SUCCESS,
         }
-    });
+    }));
 
-    view::book::full(book);
+    view::book::full(shelf);
 
-    return ExitCode::SUCCESS;
+    ExitCode::SUCCESS
 }
 
 enum BookPath {
-    Arg(PathBuf),
+    Args(Vec<PathBuf>),
     Dialog(dialog::OpenOrCreateBook),
 }
 
 fn get_book_path() -> Option<BookPath> {
-    if let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) {
-        return Some(BookPath::Arg(path));
+    let paths: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+    if !paths.is_empty() {
+        return Some(BookPath::Args(paths));
     }
 
     if let Some(path) = dialog::open_or_create_book() {
         return Some(BookPath::Dialog(path));
     }
 
-    return None;
+    None
 }
 
-fn get_book() -> Option<Result<Book<'static>, BookMakerError>> {
+/// Books given as arguments are layered in order, the first one at the bottom.
+fn get_books() -> Option<Result<Vec<Book>, BookMakerError>> {
     match get_book_path()? {
-        BookPath::Arg(path_buf) => Some(Book::try_from(path_buf)),
+        BookPath::Args(paths) => Some(paths.into_iter().map(Book::try_from).collect()),
         BookPath::Dialog(open_or_create_book) => match open_or_create_book {
-            dialog::OpenOrCreateBook::Open(path_buf) => Some(Book::try_from(path_buf)),
-            dialog::OpenOrCreateBook::Create(path_buf) => Some(book::write_default_book(&path_buf)),
+            dialog::OpenOrCreateBook::Open(path_buf) => {
+                Some(Book::try_from(path_buf).map(|book| vec![book]))
+            }
+            dialog::OpenOrCreateBook::Create(path_buf) => {
+                Some(book::write_default_book(&path_buf).map(|book| vec![book]))
+            }
         },
     }
 }
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+/// An ability score, kept between `MIN_SCORE` and `MAX_SCORE`, also when it is read
+/// from a character file.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
-#[serde(default, transparent)]
+#[serde(from = "isize", into = "isize")]
 pub struct Ability {
     score: isize,
 }
@@ -38,6 +40,11 @@ impl Ability {
     pub fn from_modifier_up(modifier: isize) -> Self {
         Self::from_score(modifier.saturating_mul(2).saturating_add(11))
     }
+
+    /// The modifier as it is written on a sheet, `+2` or `-1`.
+    pub fn modifier_text(&self) -> String {
+        format!("{:+}", self.modifier())
+    }
 }
 
 impl Default for Ability {
@@ -46,6 +53,18 @@ impl Default for Ability {
     }
 }
 
+impl From<isize> for Ability {
+    fn from(score: isize) -> Self {
+        Self::from_score(score)
+    }
+}
+
+impl From<Ability> for isize {
+    fn from(ability: Ability) -> Self {
+        ability.score
+    }
+}
+
 impl std::ops::Add for Ability {
     type Output = Ability;
     fn add(self, rhs: Self) -> Self::Output {
@@ -60,6 +79,100 @@ impl std::ops::Sub for Ability {
     }
 }
 
+/// The abilities every character has a score in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AbilityName {
+    Kinetics,
+    Coordination,
+    Grit,
+    Ingenuity,
+    Influence,
+    Astralic,
+}
+
+impl AbilityName {
+    pub const ALL: [AbilityName; 6] = [
+        AbilityName::Kinetics,
+        AbilityName::Coordination,
+        AbilityName::Grit,
+        AbilityName::Ingenuity,
+        AbilityName::Influence,
+        AbilityName::Astralic,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AbilityName::Kinetics => "Kinetics",
+            AbilityName::Coordination => "Coordination",
+            AbilityName::Grit => "Grit",
+            AbilityName::Ingenuity => "Ingenuity",
+            AbilityName::Influence => "Influence",
+            AbilityName::Astralic => "Astralic",
+        }
+    }
+}
+
+impl TryFrom<&str> for AbilityName {
+    type Error = ();
+    /// Matches names the way sheets write them, ignoring case and surrounding spaces.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+        AbilityName::ALL
+            .into_iter()
+            .find(|name| name.as_str().eq_ignore_ascii_case(value))
+            .ok_or(())
+    }
+}
+
+impl std::fmt::Display for AbilityName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A character's score in each ability.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct Abilities {
+    pub kinetics: Ability,
+    pub coordination: Ability,
+    pub grit: Ability,
+    pub ingenuity: Ability,
+    pub influence: Ability,
+    pub astralic: Ability,
+}
+
+impl Abilities {
+    pub fn get(&self, name: AbilityName) -> Ability {
+        match name {
+            AbilityName::Kinetics => self.kinetics,
+            AbilityName::Coordination => self.coordination,
+            AbilityName::Grit => self.grit,
+            AbilityName::Ingenuity => self.ingenuity,
+            AbilityName::Influence => self.influence,
+            AbilityName::Astralic => self.astralic,
+        }
+    }
+
+    pub fn get_mut(&mut self, name: AbilityName) -> &mut Ability {
+        match name {
+            AbilityName::Kinetics => &mut self.kinetics,
+            AbilityName::Coordination => &mut self.coordination,
+            AbilityName::Grit => &mut self.grit,
+            AbilityName::Ingenuity => &mut self.ingenuity,
+            AbilityName::Influence => &mut self.influence,
+            AbilityName::Astralic => &mut self.astralic,
+        }
+    }
+
+    /// Every ability with its score, in the order of `AbilityName::ALL`.
+    pub fn iter(&self) -> impl Iterator<Item = (AbilityName, Ability)> + '_ {
+        AbilityName::ALL
+            .into_iter()
+            .map(|name| (name, self.get(name)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,4 +277,25 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn abilities_read_as_sheets_name_them() {
+        for (name, expected) in [
+            ("Kinetics", Ok(AbilityName::Kinetics)),
+            (" grit ", Ok(AbilityName::Grit)),
+            ("ASTRALIC", Ok(AbilityName::Astralic)),
+            ("Strength", Err(())),
+        ] {
+            assert_eq!(
+                AbilityName::try_from(name),
+                expected,
+                "Failed for name: {}",
+                name
+            );
+        }
+
+        let abilities: Abilities = serde_json::from_str("{\"grit\": 14}").unwrap();
+        assert_eq!(abilities.get(AbilityName::Grit).modifier_text(), "+2");
+        assert_eq!(abilities.get(AbilityName::Kinetics).modifier_text(), "+0");
+    }
 }
@@ -1,175 +1,1197 @@
-use super::character::ClassLevel;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
+use super::{
+    book_file::sheet_path,
+    character::ClassLevel,
+    diagnostic::BookDiagnostic,
+    host::Host,
+    limits::Limits,
+    lint::normalize,
+    locale::Text,
+    manifest::{Manifest, MANIFEST_PATH},
+    rules::Rules,
+    schema,
+};
+
+pub use super::book_file::{write_default_book, BookMakerError};
+pub use super::vm::Src;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    Race,
+    Class,
+    Balance,
+    Cybernetic,
+    Skill,
+    Spell,
+    Astralic,
+    Weapon,
+    Armor,
+    Gear,
+}
+
+impl ContentType {
+    /// The `<type>` in `<Name>.<type>.lua`, also used as the sheet's folder.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Race => "race",
+            ContentType::Class => "class",
+            ContentType::Balance => "balance",
+            ContentType::Cybernetic => "cybernetic",
+            ContentType::Skill => "skill",
+            ContentType::Spell => "spell",
+            ContentType::Astralic => "astralic",
+            ContentType::Weapon => "weapon",
+            ContentType::Armor => "armor",
+            ContentType::Gear => "gear",
+        }
+    }
+
+    /// Name used in the sidebar and section headings.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContentType::Race => "Race",
+            ContentType::Class => "Class",
+            ContentType::Balance => "Balance",
+            ContentType::Cybernetic => "Cybernetic",
+            ContentType::Skill => "Skill",
+            ContentType::Spell => "Spell",
+            ContentType::Astralic => "Astralic",
+            ContentType::Weapon => "Weapon",
+            ContentType::Armor => "Armor",
+            ContentType::Gear => "Gear",
+        }
+    }
+}
+
+impl TryFrom<&str> for ContentType {
+    type Error = ();
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "race" => Ok(ContentType::Race),
+            "class" => Ok(ContentType::Class),
+            "balance" => Ok(ContentType::Balance),
+            "cybernetic" => Ok(ContentType::Cybernetic),
+            "skill" => Ok(ContentType::Skill),
+            "spell" => Ok(ContentType::Spell),
+            "astralic" => Ok(ContentType::Astralic),
+            "weapon" => Ok(ContentType::Weapon),
+            "armor" => Ok(ContentType::Armor),
+            "gear" => Ok(ContentType::Gear),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A loaded sheet and, once books are layered, the book it came from.
 #[derive(Debug, Clone)]
-#[repr(transparent)]
-pub struct Src(mlua::Lua);
-impl Src {
-    pub fn load<'a>(chunk: impl mlua::AsChunk<'a>) -> mlua::Result<Self> {
-        let options = mlua::LuaOptions::new();
-        let libs = mlua::StdLib::ALL_SAFE;
-        let lua = mlua::Lua::new_with(libs, options)?;
-        lua.sandbox(true)?;
-        lua.load(chunk).exec()?;
-        Ok(Src(lua))
+pub struct Sheet {
+    src: Src,
+    origin: Option<Rc<str>>,
+    overrides: Vec<Rc<str>>,
+}
+
+impl Sheet {
+    pub fn new(src: Src) -> Self {
+        Self {
+            src,
+            origin: None,
+            overrides: vec![],
+        }
     }
 
-    pub fn src(&self) -> &mlua::Lua {
-        &self.0
+    pub fn src(&self) -> &Src {
+        &self.src
     }
 
-    pub fn get<T>(&self, key: impl mlua::IntoLua) -> mlua::Result<T>
-    where
-        T: mlua::FromLua,
-    {
-        self.src().globals().get::<T>(key)
+    /// Title of the book that provided this sheet.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
     }
 
-    pub fn set<T>(&self, key: impl mlua::IntoLua, value: impl mlua::IntoLua) -> mlua::Result<()>
-    where
-        T: mlua::FromLua,
-    {
-        self.src().globals().set(key, value)
+    /// Titles of the earlier books whose sheet of the same name this one replaced.
+    pub fn overrides(&self) -> &[Rc<str>] {
+        &self.overrides
     }
+}
 
-    pub fn func(&self, key: impl mlua::IntoLua) -> mlua::Result<mlua::Function> {
-        self.get::<mlua::Function>(key)
+/// Why a value of a sheet couldn't be read, as opposed to the sheet not defining it.
+#[derive(Debug, Clone)]
+pub struct PageError {
+    /// The global or function the value comes from.
+    pub key: Box<str>,
+    pub level: Option<ClassLevel>,
+    pub message: Box<str>,
+    /// Where in the sheet's Lua it went wrong, empty when no Lua ran.
+    pub traceback: Box<str>,
+}
+
+impl PageError {
+    pub fn new(key: &str, message: impl Into<Box<str>>) -> Self {
+        Self {
+            key: key.into(),
+            level: None,
+            message: message.into(),
+            traceback: Box::default(),
+        }
     }
 
-    pub fn call<T>(&self, key: impl mlua::IntoLua, args: impl mlua::IntoLuaMulti) -> mlua::Result<T>
-    where
-        T: mlua::FromLuaMulti,
-    {
-        self.func(key)?.call(args)
+    /// Splits the traceback mlua adds to Lua errors off the message.
+    pub fn lua(key: &str, err: &mlua::Error) -> Self {
+        let (message, traceback) = match err {
+            mlua::Error::CallbackError { traceback, cause } => {
+                (cause.to_string(), traceback.clone())
+            }
+            err => {
+                let text = err.to_string();
+                match text.split_once("\nstack traceback:\n") {
+                    Some((message, traceback)) => (message.to_string(), traceback.to_string()),
+                    None => (text, String::new()),
+                }
+            }
+        };
+        Self {
+            traceback: traceback.into(),
+            ..Self::new(key, message)
+        }
+    }
+
+    pub fn with_level(self, level: ClassLevel) -> Self {
+        Self {
+            level: Some(level),
+            ..self
+        }
     }
 }
 
-pub trait Page<'a>: std::fmt::Debug + Clone {
-    fn new(name: &'a str, src: &'a Src) -> Self
+impl std::error::Error for PageError {}
+
+impl std::fmt::Display for PageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.key)?;
+        if let Some(level) = self.level {
+            write!(f, " at class level {}", level)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Options to pick one from, keyed by the name of the choice.
+pub type Choices = BTreeMap<Box<str>, Vec<Box<str>>>;
+
+/// The value of a fallible accessor, or the default when it is absent or broken.
+fn or_default<T: Default>(value: Result<Option<T>, PageError>) -> T {
+    value.ok().flatten().unwrap_or_default()
+}
+
+/// A handle on one sheet of a section. Handles own their name and sheet through `Rc`s,
+/// so they are cheap to clone, can be moved into callbacks and outlive the book they
+/// were read from.
+pub trait Page: std::fmt::Debug + Clone {
+    fn new(name: Rc<str>, sheet: Rc<Sheet>) -> Self
     where
         Self: Sized;
     fn name(&self) -> &str;
-    fn src(&self) -> &Src;
+    fn sheet(&self) -> &Sheet;
+
+    fn src(&self) -> &Src {
+        self.sheet().src()
+    }
+
+    fn origin(&self) -> Option<&str> {
+        self.sheet().origin()
+    }
+
+    fn overrides(&self) -> &[Rc<str>] {
+        self.sheet().overrides()
+    }
+
+    /// A global of the sheet, `None` when it isn't defined.
+    fn value<T: mlua::FromLua>(&self, key: &str) -> Result<Option<T>, PageError> {
+        self.src().try_get(key)
+    }
+
+    /// What a level dependent function returns, `None` when it isn't defined or returns nil.
+    fn value_at<T: mlua::FromLua>(
+        &self,
+        key: &str,
+        class_level: ClassLevel,
+    ) -> Result<Option<T>, PageError> {
+        self.src()
+            .try_call(key, class_level)
+            .map_err(|err| err.with_level(class_level))
+    }
+
+    /// A text global in every locale the sheet gives it in.
+    fn try_text(&self, key: &str) -> Result<Option<Text>, PageError> {
+        self.value(key)
+    }
+
+    /// The description in the user's locale, or the closest one the sheet has.
+    fn try_description(&self) -> Result<Option<Box<str>>, PageError> {
+        let text = self.try_text("Description")?;
+        Ok(text.map(|text| text.localized().into()))
+    }
+
+    fn try_astralic_types(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("AstralicTypes")
+    }
+
+    fn try_saving_throws(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("SavingThrows")
+    }
+
+    fn try_skills(&self, class_level: ClassLevel) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value_at("Skills", class_level)
+    }
+
+    fn try_cybernetics(&self, class_level: ClassLevel) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value_at("Cybernetics", class_level)
+    }
+
+    /// Choices the sheet offers at exactly this level, rather than up to it like `Skills`,
+    /// each a name and the options to pick one from.
+    fn try_choices(&self, class_level: ClassLevel) -> Result<Option<Choices>, PageError> {
+        self.value_at("Choices", class_level)
+    }
+
+    fn try_health(&self, class_level: ClassLevel) -> Result<Option<usize>, PageError> {
+        self.value_at("Health", class_level)
+    }
+
+    fn try_armor_rating(&self, class_level: ClassLevel) -> Result<Option<usize>, PageError> {
+        self.value_at("ArmorRating", class_level)
+    }
+
+    fn try_spell_level(&self, class_level: ClassLevel) -> Result<Option<usize>, PageError> {
+        self.value_at("SpellLevel", class_level)
+    }
 
     fn description(&self) -> Box<str> {
-        self.src().get("Description").unwrap_or_default()
+        or_default(self.try_description())
     }
 
     fn astralic_types(&self) -> Vec<Box<str>> {
-        self.src().get("AstralicTypes").unwrap_or_default()
+        or_default(self.try_astralic_types())
     }
 
     fn saving_throws(&self) -> Vec<Box<str>> {
-        self.src().get("SavingThrows").unwrap_or_default()
+        or_default(self.try_saving_throws())
     }
 
     fn skills(&self, class_level: ClassLevel) -> Vec<Box<str>> {
-        self.src().call("Skills", class_level).unwrap_or_default()
+        or_default(self.try_skills(class_level))
     }
 
     fn cybernetics(&self, class_level: ClassLevel) -> Vec<Box<str>> {
-        self.src()
-            .call("Cybernetics", class_level)
-            .unwrap_or_default()
+        or_default(self.try_cybernetics(class_level))
+    }
+
+    fn choices(&self, class_level: ClassLevel) -> Choices {
+        or_default(self.try_choices(class_level))
     }
 
     fn health(&self, class_level: ClassLevel) -> usize {
-        self.src().call("Health", class_level).unwrap_or_default()
+        or_default(self.try_health(class_level))
     }
 
     fn armor_rating(&self, class_level: ClassLevel) -> usize {
-        self.src()
-            .call("ArmorRating", class_level)
-            .unwrap_or_default()
+        or_default(self.try_armor_rating(class_level))
     }
 
     fn spell_level(&self, class_level: ClassLevel) -> usize {
-        self.src()
-            .call("SpellLevel", class_level)
-            .unwrap_or_default()
+        or_default(self.try_spell_level(class_level))
     }
 }
 
 macro_rules! simple_sheets {
-    ($($name:ident),*) => {
+    ($($(#[$meta:meta])* $name:ident),*) => {
         $(
+            $(#[$meta])*
             #[derive(Debug, Clone)]
-            pub struct $name<'a> {
-                name: &'a str,
-                src: &'a Src,
+            pub struct $name {
+                name: Rc<str>,
+                sheet: Rc<Sheet>,
             }
 
-            impl<'a> Page<'a> for $name<'a> {
-                fn new(name: &'a str, src: &'a Src) -> Self {
-                    Self { name, src }
+            impl Page for $name {
+                fn new(name: Rc<str>, sheet: Rc<Sheet>) -> Self {
+                    Self { name, sheet }
                 }
 
                 fn name(&self) -> &str {
-                    self.name
+                    &self.name
                 }
 
-                fn src(&self) -> &Src {
-                    self.src
+                fn sheet(&self) -> &Sheet {
+                    &self.sheet
                 }
             }
 
         )*
     };
 }
-simple_sheets!(RaceSheet, ClassSheet, BalanceSheet, CyberneticSheet);
+simple_sheets!(
+    /// A `<Name>.race.lua` sheet.
+    ///
+    /// ```lua
+    /// Description = "Who they are and where they come from."
+    /// Speed = 30                        -- distance moved per action point
+    /// Size = "Medium"
+    /// Age = "Adults at 18, rarely older than 100."
+    /// Languages = { "Common", "Binary" }
+    /// ```
+    RaceSheet,
+    ClassSheet,
+    BalanceSheet,
+    /// A `<Name>.cybernetic.lua` sheet.
+    ///
+    /// ```lua
+    /// Description = "What the implant looks like and does."
+    /// BodyPart = "Arm"                  -- where the implant is installed
+    /// Cost = 2                          -- cybernetic points spent to install it
+    /// Effects = { "Advantage on Grit saving throws" }
+    /// ```
+    CyberneticSheet,
+    /// A `<Name>.skill.lua` sheet, the rules text behind a name in a class's `Skills`.
+    ///
+    /// ```lua
+    /// Description = "What the skill lets you do."
+    /// Prerequisites = { "Drunken Reflexes" }
+    /// ActionCost = 1                    -- action points to use it, 0 when it is passive
+    /// UsesPerRest = 2                   -- leave out when it can be used at will
+    /// Tags = { "Reaction", "Brew" }
+    /// ```
+    SkillSheet,
+    /// A `<Name>.spell.lua` sheet.
+    ///
+    /// ```lua
+    /// Description = "What happens when it is cast."
+    /// Level = 1                         -- spell level, 0 for cantrips
+    /// AstralicType = "Fire"
+    /// ActionPoints = 2
+    /// Range = "60 feet"
+    /// Duration = "Instant"
+    /// ```
+    SpellSheet,
+    /// A `<Name>.astralic.lua` sheet, the meaning behind a name in `AstralicTypes`.
+    ///
+    /// ```lua
+    /// Description = "Where the power comes from and how it feels."
+    /// CastingAbility = "Ingenuity"      -- ability spells of this type are cast with
+    /// Opposed = { "Light" }
+    /// Synergistic = { "Digital" }
+    /// ```
+    AstralicSheet,
+    /// A `<Name>.weapon.lua` sheet.
+    ///
+    /// ```lua
+    /// Description = "What it looks like and how it is held."
+    /// Damage = "1d4 piercing"
+    /// Properties = { "Finesse", "Light", "Thrown" }
+    /// Mastery = "Dagger"                -- mastery category it counts towards
+    /// Cost = 2
+    /// Weight = 1
+    /// ```
+    WeaponSheet,
+    /// A `<Name>.armor.lua` sheet.
+    ///
+    /// ```lua
+    /// Description = "What it is made of and how it is worn."
+    /// ArmorRating = 2                   -- added to the wearer's Armor Rating
+    /// Requirements = { "Grit 13" }
+    /// Cost = 50
+    /// Weight = 20
+    /// ```
+    ArmorSheet,
+    /// A `<Name>.gear.lua` sheet, any item that is neither a weapon nor armor.
+    ///
+    /// ```lua
+    /// Description = "What it is and what it is good for."
+    /// Cost = 1
+    /// Weight = 0.5
+    /// ```
+    GearSheet
+);
+
+/// Something that can be bought and carried.
+pub trait Item: Page {
+    fn try_cost(&self) -> Result<Option<usize>, PageError> {
+        self.value("Cost")
+    }
+
+    fn try_weight(&self) -> Result<Option<f64>, PageError> {
+        self.value("Weight")
+    }
+
+    fn cost(&self) -> usize {
+        or_default(self.try_cost())
+    }
+
+    fn weight(&self) -> f64 {
+        or_default(self.try_weight())
+    }
+}
+
+impl Item for WeaponSheet {}
+impl Item for ArmorSheet {}
+impl Item for GearSheet {}
+
+impl RaceSheet {
+    pub fn try_speed(&self) -> Result<Option<usize>, PageError> {
+        self.value("Speed")
+    }
+
+    pub fn try_size(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("Size")
+    }
+
+    pub fn try_age(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("Age")
+    }
+
+    pub fn try_languages(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Languages")
+    }
+
+    pub fn speed(&self) -> usize {
+        or_default(self.try_speed())
+    }
+
+    pub fn size(&self) -> Box<str> {
+        or_default(self.try_size())
+    }
+
+    pub fn age(&self) -> Box<str> {
+        or_default(self.try_age())
+    }
+
+    pub fn languages(&self) -> Vec<Box<str>> {
+        or_default(self.try_languages())
+    }
+}
+
+impl ClassSheet {
+    /// Balances this class may be paired with, empty when any balance goes.
+    pub fn try_balances(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Balances")
+    }
+
+    /// Lowest ability scores, by ability name, a character that started in another class
+    /// needs to take this one.
+    pub fn try_multiclass_prerequisites(
+        &self,
+    ) -> Result<Option<BTreeMap<Box<str>, isize>>, PageError> {
+        self.value("MulticlassPrerequisites")
+    }
+
+    pub fn balances(&self) -> Vec<Box<str>> {
+        or_default(self.try_balances())
+    }
+
+    pub fn multiclass_prerequisites(&self) -> BTreeMap<Box<str>, isize> {
+        or_default(self.try_multiclass_prerequisites())
+    }
+}
+
+impl CyberneticSheet {
+    pub fn try_body_part(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("BodyPart")
+    }
+
+    pub fn try_cost(&self) -> Result<Option<usize>, PageError> {
+        self.value("Cost")
+    }
+
+    pub fn try_effects(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Effects")
+    }
+
+    pub fn body_part(&self) -> Box<str> {
+        or_default(self.try_body_part())
+    }
+
+    pub fn cost(&self) -> usize {
+        or_default(self.try_cost())
+    }
+
+    pub fn effects(&self) -> Vec<Box<str>> {
+        or_default(self.try_effects())
+    }
+}
+
+impl SkillSheet {
+    pub fn try_prerequisites(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Prerequisites")
+    }
+
+    pub fn try_action_cost(&self) -> Result<Option<usize>, PageError> {
+        self.value("ActionCost")
+    }
+
+    /// How often it can be used between rests, `None` when there is no limit.
+    pub fn try_uses_per_rest(&self) -> Result<Option<usize>, PageError> {
+        self.value("UsesPerRest")
+    }
+
+    pub fn try_tags(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Tags")
+    }
+
+    pub fn prerequisites(&self) -> Vec<Box<str>> {
+        or_default(self.try_prerequisites())
+    }
+
+    pub fn action_cost(&self) -> usize {
+        or_default(self.try_action_cost())
+    }
+
+    /// How often it can be used between rests, `None` when there is no limit.
+    pub fn uses_per_rest(&self) -> Option<usize> {
+        self.try_uses_per_rest().ok().flatten()
+    }
+
+    pub fn tags(&self) -> Vec<Box<str>> {
+        or_default(self.try_tags())
+    }
+}
+
+impl SpellSheet {
+    pub fn try_level(&self) -> Result<Option<usize>, PageError> {
+        self.value("Level")
+    }
+
+    pub fn try_astralic_type(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("AstralicType")
+    }
+
+    pub fn try_action_points(&self) -> Result<Option<usize>, PageError> {
+        self.value("ActionPoints")
+    }
+
+    pub fn try_range(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("Range")
+    }
+
+    pub fn try_duration(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("Duration")
+    }
+
+    pub fn level(&self) -> usize {
+        or_default(self.try_level())
+    }
+
+    pub fn astralic_type(&self) -> Box<str> {
+        or_default(self.try_astralic_type())
+    }
+
+    pub fn action_points(&self) -> usize {
+        or_default(self.try_action_points())
+    }
+
+    pub fn range(&self) -> Box<str> {
+        or_default(self.try_range())
+    }
+
+    pub fn duration(&self) -> Box<str> {
+        or_default(self.try_duration())
+    }
+}
+
+impl AstralicSheet {
+    pub fn try_casting_ability(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("CastingAbility")
+    }
+
+    pub fn try_opposed(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Opposed")
+    }
+
+    pub fn try_synergistic(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Synergistic")
+    }
+
+    pub fn casting_ability(&self) -> Box<str> {
+        or_default(self.try_casting_ability())
+    }
+
+    pub fn opposed(&self) -> Vec<Box<str>> {
+        or_default(self.try_opposed())
+    }
+
+    pub fn synergistic(&self) -> Vec<Box<str>> {
+        or_default(self.try_synergistic())
+    }
+}
+
+impl WeaponSheet {
+    pub fn try_damage(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("Damage")
+    }
+
+    pub fn try_properties(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Properties")
+    }
+
+    pub fn try_mastery(&self) -> Result<Option<Box<str>>, PageError> {
+        self.value("Mastery")
+    }
+
+    pub fn damage(&self) -> Box<str> {
+        or_default(self.try_damage())
+    }
+
+    pub fn properties(&self) -> Vec<Box<str>> {
+        or_default(self.try_properties())
+    }
+
+    pub fn mastery(&self) -> Box<str> {
+        or_default(self.try_mastery())
+    }
+}
+
+impl ArmorSheet {
+    /// How much wearing it adds to the Armor Rating, named apart from `Page::armor_rating`.
+    pub fn try_armor_bonus(&self) -> Result<Option<usize>, PageError> {
+        self.value("ArmorRating")
+    }
+
+    pub fn try_requirements(&self) -> Result<Option<Vec<Box<str>>>, PageError> {
+        self.value("Requirements")
+    }
+
+    pub fn armor_bonus(&self) -> usize {
+        or_default(self.try_armor_bonus())
+    }
+
+    pub fn requirements(&self) -> Vec<Box<str>> {
+        or_default(self.try_requirements())
+    }
+}
 
 #[derive(Debug)]
-pub struct Section<'a, P>
+pub struct Section<P>
 where
-    P: Page<'a>,
+    P: Page,
 {
-    sections: std::collections::HashMap<Box<str>, Src>,
-    _page: std::marker::PhantomData<&'a P>,
+    sections: std::collections::HashMap<Rc<str>, Rc<Sheet>>,
+    _page: std::marker::PhantomData<P>,
 }
 
-impl<'a, P> Section<'a, P>
+impl<P> Section<P>
 where
-    P: Page<'a>,
+    P: Page,
 {
     pub fn new() -> Self {
         Self {
             sections: std::collections::HashMap::new(),
-            _page: std::marker::PhantomData::default(),
+            _page: std::marker::PhantomData,
         }
     }
 
-    pub fn read(&'a self, name: &'a str) -> Option<P> {
-        Some(P::new(name, self.sections.get(name)?))
+    pub fn read(&self, name: &str) -> Option<P> {
+        let (name, sheet) = self.sections.get_key_value(name)?;
+        Some(P::new(Rc::clone(name), Rc::clone(sheet)))
+    }
+
+    /// Looks a sheet up by name, forgiving differences in case, spacing and apostrophes.
+    pub fn find(&self, name: &str) -> Option<P> {
+        if let Some(page) = self.read(name) {
+            return Some(page);
+        }
+        let wanted = normalize(name);
+        self.sections
+            .iter()
+            .find(|(candidate, _)| normalize(candidate) == wanted)
+            .map(|(name, sheet)| P::new(Rc::clone(name), Rc::clone(sheet)))
     }
 
     pub fn write(&mut self, name: Box<str>, src: Src) {
-        self.sections.insert(name, src);
+        self.sections.insert(name.into(), Rc::new(Sheet::new(src)));
     }
 
-    pub fn iter(&'a self) -> impl Iterator<Item = P> + 'a
-    where
-        P: 'a,
-    {
-        self.sections.iter().map(|(name, src)| P::new(name, src))
+    pub fn remove(&mut self, name: &str) -> Option<Rc<Sheet>> {
+        self.sections.remove(name)
+    }
+
+    pub fn sheets(&self) -> impl Iterator<Item = (&str, &Sheet)> {
+        self.sections
+            .iter()
+            .map(|(name, sheet)| (name.as_ref(), sheet.as_ref()))
+    }
+
+    /// Copies every sheet of `other` into this section, replacing sheets of the same name.
+    pub fn layer(&mut self, other: &Self, origin: &Rc<str>) {
+        for (name, sheet) in other.sections.iter() {
+            let mut overrides = vec![];
+            if let Some(old) = self.sections.get(name) {
+                overrides.extend(old.overrides.iter().cloned());
+                overrides.extend(old.origin.iter().cloned());
+            }
+            self.sections.insert(
+                Rc::clone(name),
+                Rc::new(Sheet {
+                    src: sheet.src.clone(),
+                    origin: Some(Rc::clone(origin)),
+                    overrides,
+                }),
+            );
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = P> + '_ {
+        self.sections
+            .iter()
+            .map(|(name, sheet)| P::new(Rc::clone(name), Rc::clone(sheet)))
     }
 }
 
 #[derive(Debug)]
-pub struct Book<'a> {
-    errors: Vec<String>,
-    pub race: Section<'a, RaceSheet<'a>>,
-    pub class: Section<'a, ClassSheet<'a>>,
-    pub balance: Section<'a, BalanceSheet<'a>>,
-    pub cybernetics: Section<'a, CyberneticSheet<'a>>,
+pub struct Book {
+    pub(super) diagnostics: Vec<BookDiagnostic>,
+    /// What checking the sheets against their schema found. Kept apart from
+    /// `diagnostics`, as a layered book checks its sheets again with the sheets of the
+    /// books beneath it to look up.
+    pub(super) checks: Vec<BookDiagnostic>,
+    /// The path each sheet was read from.
+    pub(super) paths: HashMap<(ContentType, Box<str>), Box<str>>,
+    pub(super) directory: Option<PathBuf>,
+    pub(super) manifest: Option<Manifest>,
+    pub(super) manifest_src: Option<Src>,
+    pub(super) rules: Rules,
+    /// Files that are not Lua, such as art, keyed by their path in the book.
+    pub(super) assets: BTreeMap<Box<str>, Vec<u8>>,
+    /// What the `book` module of every sheet looks at.
+    pub(super) host: Host,
+    /// Sources of the modules in `lib`, by the name sheets `require` them with.
+    pub(super) libs: BTreeMap<Box<str>, Rc<str>>,
+    /// What each book layered into this one is called in provenance and diagnostics,
+    /// bottom to top.
+    pub(super) layers: Vec<Rc<str>>,
+    /// Sources of sheets that are empty or failed to load, by the path they are saved to,
+    /// so saving the book doesn't lose them.
+    pub(super) broken: BTreeMap<Box<str>, String>,
+    /// Sheets read but not yet loaded, with the path they were read from and their source.
+    pub(super) unloaded: Vec<(Box<str>, ContentType, Box<str>, String)>,
+    pub race: Section<RaceSheet>,
+    pub class: Section<ClassSheet>,
+    pub balance: Section<BalanceSheet>,
+    pub cybernetics: Section<CyberneticSheet>,
+    pub skills: Section<SkillSheet>,
+    pub spells: Section<SpellSheet>,
+    pub astralics: Section<AstralicSheet>,
+    pub weapons: Section<WeaponSheet>,
+    pub armor: Section<ArmorSheet>,
+    pub gear: Section<GearSheet>,
 }
 
-impl Book<'_> {
+impl Book {
     pub fn new() -> Self {
         Self {
-            errors: vec![],
+            diagnostics: vec![],
+            checks: vec![],
+            paths: HashMap::new(),
+            directory: None,
+            manifest: None,
+            manifest_src: None,
+            rules: Rules::default(),
+            assets: BTreeMap::new(),
+            host: Host::new(),
+            libs: BTreeMap::new(),
+            broken: BTreeMap::new(),
+            layers: vec![],
+            unloaded: vec![],
             race: Section::new(),
             class: Section::new(),
             balance: Section::new(),
             cybernetics: Section::new(),
+            skills: Section::new(),
+            spells: Section::new(),
+            astralics: Section::new(),
+            weapons: Section::new(),
+            armor: Section::new(),
+            gear: Section::new(),
+        }
+    }
+
+    /// An empty book whose sheets will run within `limits`.
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            host: Host::with_limits(limits),
+            ..Self::new()
+        }
+    }
+
+    /// Everything that went wrong while reading the book and checking its sheets.
+    pub fn diagnostics(&self) -> Vec<BookDiagnostic> {
+        self.diagnostics
+            .iter()
+            .chain(self.checks.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// The `book.lua` manifest, `None` when the book has none or it could not be loaded.
+    pub fn manifest(&self) -> Option<&Manifest> {
+        self.manifest.as_ref()
+    }
+
+    /// The rules for building characters, as the manifests of this book and any it was
+    /// layered on set them.
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Title from the manifest, falling back to a placeholder.
+    pub fn title(&self) -> &str {
+        match &self.manifest {
+            Some(manifest) if !manifest.title.is_empty() => &manifest.title,
+            _ => "Untitled book",
+        }
+    }
+
+    /// The folder this book was read from, when it is not a zip.
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    pub fn assets(&self) -> &BTreeMap<Box<str>, Vec<u8>> {
+        &self.assets
+    }
+
+    /// Every sheet of every section.
+    pub fn sheets(&self) -> Vec<(ContentType, &str, &Sheet)> {
+        let mut sheets = vec![];
+        sheets.extend(
+            self.race
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Race, name, sheet)),
+        );
+        sheets.extend(
+            self.class
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Class, name, sheet)),
+        );
+        sheets.extend(
+            self.balance
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Balance, name, sheet)),
+        );
+        sheets.extend(
+            self.cybernetics
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Cybernetic, name, sheet)),
+        );
+        sheets.extend(
+            self.skills
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Skill, name, sheet)),
+        );
+        sheets.extend(
+            self.spells
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Spell, name, sheet)),
+        );
+        sheets.extend(
+            self.astralics
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Astralic, name, sheet)),
+        );
+        sheets.extend(
+            self.weapons
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Weapon, name, sheet)),
+        );
+        sheets.extend(
+            self.armor
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Armor, name, sheet)),
+        );
+        sheets.extend(
+            self.gear
+                .sheets()
+                .map(|(name, sheet)| (ContentType::Gear, name, sheet)),
+        );
+        sheets
+    }
+
+    /// Lets the `book` module see the sheets as they are now.
+    pub(super) fn sync_host(&self) {
+        self.host.set_sheets(
+            self.sheets()
+                .into_iter()
+                .map(|(content_type, name, sheet)| {
+                    (content_type, name.into(), sheet.src().clone())
+                }),
+        );
+    }
+
+    /// Checks every sheet against the schema of its content type.
+    pub(super) fn check_sheets(&mut self) {
+        self.checks = self
+            .sheets()
+            .into_iter()
+            .filter_map(|(content_type, name, sheet)| {
+                let path = self.paths.get(&(content_type, name.into()))?;
+                Some(schema::validate(path, content_type, name, sheet.src()))
+            })
+            .flatten()
+            .collect();
+    }
+
+    pub fn remove_sheet(&mut self, content_type: ContentType, name: &str) -> Option<Rc<Sheet>> {
+        match content_type {
+            ContentType::Race => self.race.remove(name),
+            ContentType::Class => self.class.remove(name),
+            ContentType::Balance => self.balance.remove(name),
+            ContentType::Cybernetic => self.cybernetics.remove(name),
+            ContentType::Skill => self.skills.remove(name),
+            ContentType::Spell => self.spells.remove(name),
+            ContentType::Astralic => self.astralics.remove(name),
+            ContentType::Weapon => self.weapons.remove(name),
+            ContentType::Armor => self.armor.remove(name),
+            ContentType::Gear => self.gear.remove(name),
+        }
+    }
+
+    /// Puts `other` on top of this book. Sheets its manifest lists under `Remove` are
+    /// taken out first, then its sheets are added, replacing any of the same name. Its
+    /// manifest, modules and assets replace those of the same name as well.
+    pub fn layer(&mut self, other: &Self) {
+        // books can share a title, a later one is told apart by its place in the stack
+        let origin: Rc<str> = match self.layers.iter().any(|layer| &**layer == other.title()) {
+            false => other.title().into(),
+            true => format!("{} (book {})", other.title(), self.layers.len() + 1).into(),
+        };
+        self.layers.push(Rc::clone(&origin));
+
+        if let Some(manifest) = other.manifest() {
+            self.rules.apply(&manifest.rules);
+            for (content_type, name) in manifest.removes.iter() {
+                if self.remove_sheet(*content_type, name).is_none() {
+                    self.diagnostics.push(
+                        BookDiagnostic::warning(
+                            format!("{}/{}", origin, MANIFEST_PATH),
+                            format!(
+                                "removes {} \"{}\", which no earlier book has",
+                                content_type.label(),
+                                name
+                            ),
+                        )
+                        .with_sheet(*content_type, name.clone()),
+                    );
+                }
+            }
+        }
+
+        self.race.layer(&other.race, &origin);
+        self.class.layer(&other.class, &origin);
+        self.balance.layer(&other.balance, &origin);
+        self.cybernetics.layer(&other.cybernetics, &origin);
+        self.skills.layer(&other.skills, &origin);
+        self.spells.layer(&other.spells, &origin);
+        self.astralics.layer(&other.astralics, &origin);
+        self.weapons.layer(&other.weapons, &origin);
+        self.armor.layer(&other.armor, &origin);
+        self.gear.layer(&other.gear, &origin);
+        self.sync_host();
+        other.host.layer_into(&self.host);
+
+        self.paths.extend(
+            other
+                .paths
+                .iter()
+                .map(|(sheet, path)| (sheet.clone(), format!("{}/{}", origin, path).into())),
+        );
+        // sheets of `other` may look up sheets only the books beneath it have
+        self.check_sheets();
+        self.diagnostics
+            .extend(other.diagnostics.iter().map(|diagnostic| BookDiagnostic {
+                path: format!("{}/{}", origin, diagnostic.path).into(),
+                ..diagnostic.clone()
+            }));
+
+        // what isn't a sheet is carried too, so the layered book saves whole
+        if other.manifest.is_some() {
+            self.manifest = other.manifest.clone();
+            self.manifest_src = other.manifest_src.clone();
+        }
+        self.libs.extend(
+            other
+                .libs
+                .iter()
+                .map(|(name, source)| (name.clone(), Rc::clone(source))),
+        );
+        self.assets.extend(
+            other
+                .assets
+                .iter()
+                .map(|(path, bytes)| (path.clone(), bytes.clone())),
+        );
+        for (content_type, name, _) in other.sheets() {
+            self.broken.remove(sheet_path(content_type, name).as_str());
+        }
+        self.broken.extend(
+            other
+                .broken
+                .iter()
+                .map(|(path, source)| (path.clone(), source.clone())),
+        );
+    }
+}
+
+impl Book {
+    /// Spells a class can cast at `class_level` when paired with a balance: those of one
+    /// of the class's Astralic types, up to the balance's spell level, lowest level first.
+    /// Spells and sheets with a broken value are left out.
+    pub fn castable_spells(
+        &self,
+        class: &str,
+        balance: &str,
+        class_level: ClassLevel,
+    ) -> Vec<SpellSheet> {
+        let (class, balance) = match (self.class.find(class), self.balance.find(balance)) {
+            (Some(class), Some(balance)) => (class, balance),
+            _ => return vec![],
+        };
+
+        // a broken spell level or list of types casts nothing rather than cantrips
+        let max_level = match balance.try_spell_level(class_level) {
+            Ok(max_level) => max_level.unwrap_or_default(),
+            Err(_) => return vec![],
+        };
+        let astralic_types: Vec<String> = match class.try_astralic_types() {
+            Ok(astralic_types) => astralic_types
+                .unwrap_or_default()
+                .iter()
+                .map(|astralic_type| normalize(astralic_type))
+                .collect(),
+            Err(_) => return vec![],
+        };
+        let mut spells: Vec<SpellSheet> = self
+            .spells
+            .iter()
+            .filter(
+                |spell| match (spell.try_level(), spell.try_astralic_type()) {
+                    (Ok(level), Ok(Some(astralic_type))) => {
+                        level.unwrap_or_default() <= max_level
+                            && astralic_types.contains(&normalize(&astralic_type))
+                    }
+                    _ => false,
+                },
+            )
+            .collect();
+        spells.sort_by(|a, b| {
+            a.level()
+                .cmp(&b.level())
+                .then_with(|| a.name().cmp(b.name()))
+        });
+        spells
+    }
+
+    /// Classes with `astralic_type` among their `AstralicTypes`, by name.
+    pub fn classes_using(&self, astralic_type: &str) -> Vec<ClassSheet> {
+        let astralic_type = normalize(astralic_type);
+        let mut classes: Vec<ClassSheet> = self
+            .class
+            .iter()
+            .filter(|class| {
+                class
+                    .astralic_types()
+                    .iter()
+                    .any(|name| normalize(name) == astralic_type)
+            })
+            .collect();
+        classes.sort_by(|a, b| a.name().cmp(b.name()));
+        classes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(manifest: &str, classes: &[(&str, &str)]) -> Book {
+        let mut book = Book::new();
+        let src = Src::load(MANIFEST_PATH, manifest).unwrap();
+        book.manifest = Some(Manifest::read(&src).0);
+        for (name, source) in classes {
+            book.class
+                .write((*name).into(), Src::load(name, *source).unwrap());
         }
+        book
+    }
+
+    #[test]
+    fn later_books_override_and_remove_sheets() {
+        let base = book(
+            "Title = \"Base\"\n",
+            &[
+                ("Pyro", "function Health(level)\n    return level\nend\n"),
+                ("Brute", ""),
+            ],
+        );
+        let mut expansion = book(
+            "Title = \"Expansion\"\nRemove = { class = { \"Brute\", \"Nobody\" } }\n",
+            &[(
+                "Pyro",
+                "function Health(level)\n    return level * 2\nend\n",
+            )],
+        );
+        expansion
+            .diagnostics
+            .push(BookDiagnostic::error("class/Odd.class.lua", "broken"));
+
+        let mut merged = Book::new();
+        merged.layer(&base);
+        merged.layer(&expansion);
+
+        assert!(merged.class.find("Brute").is_none());
+        assert_eq!(
+            merged.class.find("Pyro").map(|pyro| pyro.health(3)),
+            Some(6)
+        );
+        let (_, pyro) = merged.class.sheets().next().unwrap();
+        assert_eq!(pyro.origin(), Some("Expansion"));
+        assert_eq!(pyro.overrides(), &[Rc::from("Base")]);
+
+        let diagnostics: Vec<String> = merged
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| format!("{}: {}", diagnostic.path, diagnostic.message))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "Expansion/book.lua: removes Class \"Nobody\", which no earlier book has",
+                "Expansion/class/Odd.class.lua: broken",
+            ]
+        );
+    }
+
+    #[test]
+    fn books_sharing_a_title_keep_apart() {
+        let pyro = [("Pyro", "")];
+        let mut first = book("Title = \"Homebrew\"\n", &pyro);
+        first
+            .diagnostics
+            .push(BookDiagnostic::error("class/Odd.class.lua", "broken"));
+        let second = book("Title = \"Homebrew\"\n", &pyro);
+
+        let mut merged = Book::new();
+        merged.layer(&first);
+        merged.layer(&second);
+
+        let (_, pyro) = merged.class.sheets().next().unwrap();
+        assert_eq!(pyro.origin(), Some("Homebrew (book 2)"));
+        assert_eq!(pyro.overrides(), &[Rc::from("Homebrew")]);
+        assert_eq!(
+            &*merged.diagnostics()[0].path,
+            "Homebrew/class/Odd.class.lua"
+        );
     }
 }
@@ -1,8 +1,20 @@
-use std::{fs::File, io, path::Path};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use zip::ZipArchive;
+use zip::{read::ZipFile, result::ZipError, write::SimpleFileOptions, ZipArchive, ZipWriter};
 
-use super::book::{Book, Src};
+use super::{
+    book::{Book, ContentType, Src},
+    diagnostic::BookDiagnostic,
+    host::{module_path, LIB_DIR},
+    limits::Limits,
+    manifest::{Manifest, MANIFEST_PATH},
+    rules::Rules,
+};
 
 pub const DEFAULT_BOOK_BYTES: &[u8] =
     include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/base_game.zip"));
@@ -17,36 +29,51 @@ impl Default for Book {
 }
 
 pub fn write_default_book(path: &Path) -> Result<Book, BookMakerError> {
-    File::create_new(path)?.write(DEFAULT_BOOK_BYTES)?;
+    File::create_new(path)?.write_all(DEFAULT_BOOK_BYTES)?;
     Ok(Book::default())
 }
 
-/// --- File Reader
+// --- Book Makers --- From / TryFrom
 
-enum ContentType {
-    Race,
-    Class,
-    Balance,
-    Cybernetic,
-}
+impl Book {
+    /// Reads a book folder or zip, with every sheet's Lua bounded by `limits`.
+    pub fn open(path: &Path, limits: Limits) -> Result<Self, BookMakerError> {
+        if path.is_dir() {
+            let mut book = Self::with_limits(limits);
+            book.write_directory(path, path)?;
+            book.load_sheets();
+            book.check_manifest();
+            book.directory = Some(path.to_path_buf());
+            return Ok(book);
+        }
+        Ok(Self::from_zip(
+            &mut ZipArchive::new(File::open(path)?)?,
+            limits,
+        ))
+    }
 
-impl TryFrom<&str> for ContentType {
-    type Error = ();
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "class" => Ok(ContentType::Class),
-            "balance" => Ok(ContentType::Balance),
-            _ => Err(()),
+    pub fn from_zip<R: io::Read + io::Seek>(archive: &mut ZipArchive<R>, limits: Limits) -> Self {
+        let mut book = Self::with_limits(limits);
+        let root = zip_root(archive.file_names());
+        for file_number in 0..archive.len() {
+            match archive.by_index(file_number) {
+                Ok(file) => book.write_zip_file(file, root.as_deref()),
+                Err(err) => book.diagnostics.push(BookDiagnostic::error(
+                    format!("entry #{}", file_number),
+                    err.to_string(),
+                )),
+            };
         }
+        book.load_sheets();
+        book.check_manifest();
+        book
     }
 }
 
-/// --- Book Makers --- From / TryFrom
-
 impl TryFrom<&Path> for Book {
     type Error = BookMakerError;
     fn try_from(value: &Path) -> Result<Self, Self::Error> {
-        Self::try_from(File::open(value)?)
+        Self::open(value, Limits::default())
     }
 }
 
@@ -66,86 +93,402 @@ impl TryFrom<File> for Book {
 
 impl<R: io::Read + io::Seek> From<&mut ZipArchive<R>> for Book {
     fn from(value: &mut ZipArchive<R>) -> Self {
-        let mut book = Self::new();
-        for file_number in 0..value.len() {
-            match value.by_index(file_number) {
-                Ok(mut file) => read_file(&mut book, &mut file),
-                Err(err) => book.errors.push(err.to_string()),
-            };
+        Self::from_zip(value, Limits::default())
+    }
+}
+
+// --- File Reader
+
+/// Splits `<Name>.<type>.lua` into the sheet name and its content type.
+fn sheet_name(path: &Path) -> Result<(Box<str>, ContentType), String> {
+    if path.extension().and_then(|osstr| osstr.to_str()) != Some("lua") {
+        return Err("not a .lua file".to_string());
+    }
+
+    let file_name = match path.file_stem() {
+        Some(file_name) => Path::new(file_name),
+        None => return Err("file has no name".to_string()),
+    };
+
+    let content_type = match file_name.extension() {
+        Some(osstr) => match ContentType::try_from(osstr.to_string_lossy().as_ref()) {
+            Ok(content_type) => content_type,
+            Err(_) => {
+                return Err(format!(
+                    "unknown content type \"{}\"",
+                    osstr.to_string_lossy()
+                ))
+            }
+        },
+        None => return Err("expected a name like <Name>.<type>.lua".to_string()),
+    };
+
+    match file_name.file_stem() {
+        Some(osstr) => Ok((
+            osstr.to_string_lossy().to_string().into_boxed_str(),
+            content_type,
+        )),
+        None => Err("sheet has no name".to_string()),
+    }
+}
+
+/// The folder a zip keeps its book in, when it was zipped with the folder around it
+/// as `zip -r game.zip game` does: the folder holding `book.lua` or, for a book
+/// without one, the only top-level folder unless that is a folder of the book itself.
+fn zip_root<'n>(names: impl Iterator<Item = &'n str>) -> Option<PathBuf> {
+    // folders end with a slash, only files say where the book is
+    let files: Vec<&Path> = names
+        .filter(|name| !name.ends_with('/'))
+        .map(Path::new)
+        .collect();
+    if let Some(manifest) = files.iter().find(|path| {
+        path.file_name() == Some(MANIFEST_PATH.as_ref()) && path.components().count() == 2
+    }) {
+        if !files.iter().any(|path| *path == Path::new(MANIFEST_PATH)) {
+            return manifest.parent().map(Path::to_path_buf);
         }
-        book
     }
+
+    let top = files.first()?.components().next()?;
+    let nested = files
+        .iter()
+        .all(|path| path.components().count() > 1 && path.components().next() == Some(top));
+    let top = top.as_os_str().to_string_lossy();
+    let own_folder = top == LIB_DIR || ContentType::try_from(top.as_ref()).is_ok();
+    (nested && !own_folder).then(|| PathBuf::from(top.as_ref()))
 }
 
-fn read_file(book: &mut Book, mut file: &mut ZipFile) {
-    let mut content = String::new();
-    match io::Read::read_to_string(&mut file, &mut content) {
-        Ok(num) => {
-            if num == 0 || content.len() == 0 {
-                return;
+/// The name sheets `require` a `lib/<name>.lua` module with.
+fn module_name(path: &Path) -> Option<Box<str>> {
+    if path.extension().and_then(|osstr| osstr.to_str()) != Some("lua") {
+        return None;
+    }
+    let name = path.strip_prefix(LIB_DIR).ok()?.with_extension("");
+    let parts: Vec<_> = name
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("/").into_boxed_str())
+}
+
+/// Editor backups and dot files that live next to sheets in a book folder.
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map(|osstr| osstr.to_string_lossy())
+        .is_some_and(|name| name.starts_with('.') || name.ends_with('~'))
+}
+
+impl Book {
+    fn write_directory(&mut self, root: &Path, directory: &Path) -> io::Result<()> {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if is_hidden(&path) {
+                continue;
             }
+            if path.is_dir() {
+                if let Err(err) = self.write_directory(root, &path) {
+                    self.diagnostics.push(BookDiagnostic::error(
+                        path.strip_prefix(root).unwrap_or(&path).to_string_lossy(),
+                        err.to_string(),
+                    ));
+                }
+                continue;
+            }
+            self.write_directory_file(root, &path);
         }
-        Err(err) => {
-            return;
+        Ok(())
+    }
+
+    fn write_directory_file(&mut self, root: &Path, path: &Path) {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        match File::open(path) {
+            Ok(mut file) => self.write_file(relative, &mut file),
+            Err(err) => self.diagnostics.push(BookDiagnostic::error(
+                relative.to_string_lossy(),
+                err.to_string(),
+            )),
         }
-    };
+    }
 
-    read_content(format, content_type, book, name, content);
-}
+    /// Reads a file of a folder book again after it was saved, created or deleted,
+    /// replacing the sheet and the diagnostics it produced last time.
+    pub fn reload_file(&mut self, path: &Path) {
+        let root = match &self.directory {
+            Some(root) => root.clone(),
+            None => return,
+        };
+        if is_hidden(path) {
+            return;
+        }
 
-impl Book<'_> {
-    fn write_zip_file(&mut self, file: zip::read::ZipFile) {
-        if !file.is_file() {
+        let relative = path.strip_prefix(&root).unwrap_or(path);
+        if module_name(relative).is_some() {
+            // any sheet may require the module, so the whole book is read again
+            match Self::open(&root, self.host.limits()) {
+                Ok(book) => *self = book,
+                Err(err) => self.diagnostics.push(BookDiagnostic::error(
+                    relative.to_string_lossy(),
+                    err.to_string(),
+                )),
+            }
             return;
         }
+        let display = relative.to_string_lossy();
+        self.diagnostics
+            .retain(|diagnostic| *diagnostic.path != *display);
+        self.assets.remove(display.as_ref());
+
+        if let Ok((name, content_type)) = sheet_name(relative) {
+            self.remove_sheet(content_type, &name);
+            self.broken.remove(sheet_path(content_type, &name).as_str());
+        }
 
-        let file_name = {
-            let file_name = match file.enclosed_name() {
-                Some(file_name) => file_name,
-                None => return,
+        let is_manifest = relative == Path::new(MANIFEST_PATH);
+        if is_manifest {
+            self.manifest = None;
+            self.manifest_src = None;
+        }
+
+        if path.is_file() {
+            self.write_directory_file(&root, path);
+        }
+        self.load_sheets();
+
+        if is_manifest {
+            self.check_manifest();
+        }
+    }
+
+    /// Loads the sheets read since the last call and checks every sheet against its schema.
+    /// Loading waits for every file to be read, as a sheet may require any module of the
+    /// book, and checking waits for every sheet to load, as they may look each other up.
+    fn load_sheets(&mut self) {
+        self.host.set_libs(
+            self.libs
+                .iter()
+                .map(|(name, source)| (name.clone(), Rc::clone(source))),
+        );
+
+        for (path, content_type, name, content) in std::mem::take(&mut self.unloaded) {
+            let saved_at: Box<str> = sheet_path(content_type, &name).into();
+            let src = match self.host.load(content_type, &name, &path, content.clone()) {
+                Ok(src) => src,
+                Err(err) => {
+                    self.broken.insert(saved_at, content);
+                    self.diagnostics
+                        .push(BookDiagnostic::lua(path, &err).with_sheet(content_type, name));
+                    continue;
+                }
             };
+            self.broken.remove(&saved_at);
+            self.paths.insert((content_type, name.clone()), path);
+            self.write_sheet(content_type, name, src);
+        }
 
-            if file_name.extension().and_then(|osstr| osstr.to_str()) != Some("lua") {
+        self.sync_host();
+        self.check_sheets();
+    }
+
+    /// Warns about a missing manifest, unless loading it already reported why.
+    fn check_manifest(&mut self) {
+        if self.manifest.is_some()
+            || self
+                .diagnostics
+                .iter()
+                .any(|diagnostic| &*diagnostic.path == MANIFEST_PATH)
+        {
+            return;
+        }
+        self.diagnostics.push(BookDiagnostic::warning(
+            MANIFEST_PATH,
+            "book has no manifest, add a book.lua with a Title, Version and SystemVersion",
+        ));
+    }
+
+    fn write_manifest(&mut self, file: &mut impl io::Read) {
+        let mut content = String::new();
+        if let Err(err) = file.read_to_string(&mut content) {
+            self.diagnostics
+                .push(BookDiagnostic::error(MANIFEST_PATH, err.to_string()));
+            return;
+        }
+
+        let src = match Src::load(MANIFEST_PATH, content) {
+            Ok(src) => src,
+            Err(err) => {
+                self.diagnostics
+                    .push(BookDiagnostic::lua(MANIFEST_PATH, &err));
                 return;
             }
+        };
+
+        let (manifest, problems) = Manifest::read(&src);
+        for problem in problems {
+            self.diagnostics
+                .push(BookDiagnostic::warning(MANIFEST_PATH, problem));
+        }
+        self.rules = Rules::default();
+        self.rules.apply(&manifest.rules);
+        self.manifest = Some(manifest);
+        self.manifest_src = Some(src);
+    }
+
+    /// Reads one entry of a zip, with its path taken relative to `root` when it has one.
+    fn write_zip_file<R: Read>(&mut self, mut file: ZipFile<'_, R>, root: Option<&Path>) {
+        if !file.is_file() {
+            return;
+        }
+
+        match file.enclosed_name() {
+            Some(path) => {
+                let relative = root
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .unwrap_or(&path);
+                self.write_file(relative, &mut file)
+            }
+            None => self.diagnostics.push(BookDiagnostic::error(
+                file.name(),
+                "entry points outside of the book",
+            )),
+        }
+    }
 
-            match file_name.file_stem() {
-                Some(file_name) => Path::new(file_name),
-                None => return,
+    fn write_file(&mut self, path: &Path, file: &mut impl io::Read) {
+        if path == Path::new(MANIFEST_PATH) {
+            self.write_manifest(file);
+            return;
+        }
+        let display = path.to_string_lossy().to_string();
+
+        if let Some(name) = module_name(path) {
+            let mut content = String::new();
+            match file.read_to_string(&mut content) {
+                Ok(_) => {
+                    self.libs.insert(name, content.into());
+                }
+                Err(err) => self
+                    .diagnostics
+                    .push(BookDiagnostic::error(display, err.to_string())),
             }
-        };
+            return;
+        }
 
-        let content_type = file_name
-            .extension()
-            .and_then(|osstr| ContentType::try_from(osstr.to_string_lossy().as_ref()).ok())
-            .unwrap_or_else(|| return);
+        if path.extension().and_then(|osstr| osstr.to_str()) != Some("lua") {
+            let mut bytes = vec![];
+            match file.read_to_end(&mut bytes) {
+                Ok(_) => {
+                    self.assets.insert(display.into_boxed_str(), bytes);
+                }
+                Err(err) => self
+                    .diagnostics
+                    .push(BookDiagnostic::error(display, err.to_string())),
+            }
+            return;
+        }
 
-        let name = file_name
-            .file_stem()
-            .map(|osstr| osstr.to_string_lossy().to_string().into_boxed_str())
-            .unwrap_or_else(|| return);
+        let (name, content_type) = match sheet_name(path) {
+            Ok(sheet) => sheet,
+            Err(message) => {
+                self.diagnostics
+                    .push(BookDiagnostic::warning(display, message));
+                return;
+            }
+        };
 
         let mut content = String::new();
-        if let Err(_) = io::Read::read_to_string(&mut file, &mut content) {
+        if let Err(err) = file.read_to_string(&mut content) {
+            self.diagnostics.push(
+                BookDiagnostic::error(display, err.to_string()).with_sheet(content_type, name),
+            );
             return;
         }
 
-        if content.is_empty() {
+        if content.trim().is_empty() {
+            self.broken
+                .insert(sheet_path(content_type, &name).into(), content);
+            self.diagnostics.push(
+                BookDiagnostic::warning(display, "sheet is empty").with_sheet(content_type, name),
+            );
             return;
         }
 
-        let src = Src::load(content).unwrap_or_else(|_| return);
+        self.unloaded
+            .push((display.into_boxed_str(), content_type, name, content));
+    }
 
+    fn write_sheet(&mut self, content_type: ContentType, name: Box<str>, src: Src) {
         match content_type {
             ContentType::Race => self.race.write(name, src),
             ContentType::Class => self.class.write(name, src),
             ContentType::Balance => self.balance.write(name, src),
             ContentType::Cybernetic => self.cybernetics.write(name, src),
+            ContentType::Skill => self.skills.write(name, src),
+            ContentType::Spell => self.spells.write(name, src),
+            ContentType::Astralic => self.astralics.write(name, src),
+            ContentType::Weapon => self.weapons.write(name, src),
+            ContentType::Armor => self.armor.write(name, src),
+            ContentType::Gear => self.gear.write(name, src),
         }
     }
 }
 
-/// --- Book Maker Error
+// --- File Writer
+
+/// `<type>/<Name>.<type>.lua`, where a sheet is read back from.
+pub(super) fn sheet_path(content_type: ContentType, name: &str) -> String {
+    format!("{0}/{1}.{0}.lua", content_type.as_str(), name)
+}
+
+impl Book {
+    /// Every file a `.book.zip` of this book holds, sorted by path. Sheets that are
+    /// empty or failed to load are kept as they were read.
+    pub fn entries(&self) -> Vec<(String, Vec<u8>)> {
+        let mut entries = vec![];
+        if let Some(src) = &self.manifest_src {
+            entries.push((MANIFEST_PATH.to_string(), src.source().as_bytes().to_vec()));
+        }
+        for (content_type, name, sheet) in self.sheets() {
+            entries.push((
+                sheet_path(content_type, name),
+                sheet.src().source().as_bytes().to_vec(),
+            ));
+        }
+        for (path, source) in self.broken.iter() {
+            // a sheet of the same name that did load is the one kept
+            if !entries
+                .iter()
+                .any(|(written, _)| written.as_str() == path.as_ref())
+            {
+                entries.push((path.to_string(), source.as_bytes().to_vec()));
+            }
+        }
+        for (name, source) in self.libs.iter() {
+            entries.push((module_path(name), source.as_bytes().to_vec()));
+        }
+        for (path, bytes) in self.assets.iter() {
+            entries.push((path.to_string(), bytes.clone()));
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries
+    }
+
+    /// Writes the book as a zip that `Book::try_from` reads back into the same book.
+    pub fn write_zip<W: io::Write + io::Seek>(&self, writer: W) -> Result<W, BookMakerError> {
+        let mut zip = ZipWriter::new(writer);
+        for (path, bytes) in self.entries() {
+            zip.start_file(path, SimpleFileOptions::default())?;
+            zip.write_all(&bytes)?;
+        }
+        Ok(zip.finish()?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), BookMakerError> {
+        self.write_zip(File::create(path)?)?;
+        Ok(())
+    }
+}
+
+// --- Book Maker Error
 
 #[derive(Debug)]
 pub enum BookMakerError {
@@ -182,3 +525,290 @@ impl From<ZipError> for BookMakerError {
         BookMakerError::Zip(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::book::Page;
+    use super::*;
+
+    fn zip_of(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(io::Cursor::new(vec![]));
+        for (path, content) in files {
+            zip.start_file(*path, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn read_zip(bytes: Vec<u8>) -> Book {
+        Book::from(&mut ZipArchive::new(io::Cursor::new(bytes)).unwrap())
+    }
+
+    #[test]
+    fn book_round_trips_through_zip() {
+        let files = [
+            (
+                "balance/Even Split.balance.lua",
+                "Description = [[\n- Half of everything\n]]\n",
+            ),
+            ("book.lua", "Title = \"Round Trip\"\nVersion = \"1.2.3\"\nSystemVersion = \"0.1.0\"\n"),
+            ("class/Tester.class.lua", "local twice = require(\"twice\")\n\nAstralicTypes = { \"Fire\" }\n\nfunction Health(class_level)\n    return twice(class_level)\nend\n"),
+            ("cybernetic/Steel Arm.cybernetic.lua", "Cost = 2\n"),
+            ("images/cover.png", "\u{89}PNG not really"),
+            ("lib/twice.lua", "return function(x)\n    return x * 2\nend\n"),
+            ("race/Human.race.lua", "Speed = 30\n"),
+        ];
+        let book = read_zip(zip_of(&files));
+        assert!(book.diagnostics().is_empty(), "{:?}", book.diagnostics());
+
+        let expected: Vec<(String, Vec<u8>)> = files
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.as_bytes().to_vec()))
+            .collect();
+        assert_eq!(book.entries(), expected);
+
+        let written = book.write_zip(io::Cursor::new(vec![])).unwrap();
+        let reread = read_zip(written.into_inner());
+        assert!(
+            reread.diagnostics().is_empty(),
+            "{:?}",
+            reread.diagnostics()
+        );
+        assert_eq!(reread.entries(), expected);
+        assert_eq!(
+            reread
+                .manifest()
+                .map(|manifest| manifest.version.to_string()),
+            Some("1.2.3".to_string())
+        );
+    }
+
+    #[test]
+    fn books_zipped_inside_their_folder_read_from_it() {
+        let book = read_zip(zip_of(&[
+            ("base_game/book.lua", "Title = \"Prefixed\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n"),
+            ("base_game/class/Tester.class.lua", "local twice = require(\"twice\")\n\nfunction Health(class_level)\n    return twice(class_level)\nend\n"),
+            ("base_game/lib/twice.lua", "return function(x)\n    return x * 2\nend\n"),
+        ]));
+        assert!(book.diagnostics().is_empty(), "{:?}", book.diagnostics());
+        assert_eq!(book.title(), "Prefixed");
+        assert_eq!(
+            book.class.find("Tester").map(|class| class.health(2)),
+            Some(4)
+        );
+
+        // a book without a manifest whose only folder is one of its own stays as it is
+        let book = read_zip(zip_of(&[(
+            "class/Tester.class.lua",
+            "Description = \"\"\n",
+        )]));
+        assert!(book.class.find("Tester").is_some());
+        assert_eq!(
+            zip_root(["game/", "game/race/Human.race.lua"].into_iter()),
+            Some(PathBuf::from("game"))
+        );
+        assert_eq!(
+            zip_root(["lib/twice.lua", "lib/more.lua"].into_iter()),
+            None
+        );
+    }
+
+    #[test]
+    fn layered_sheets_are_checked_against_the_books_beneath_them() {
+        let base = read_zip(zip_of(&[
+            (
+                "book.lua",
+                "Title = \"Base\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n",
+            ),
+            (
+                "balance/Full Caster.balance.lua",
+                "function SpellLevel(level)\n    return level\nend\n",
+            ),
+        ]));
+        let expansion = read_zip(zip_of(&[
+            ("book.lua", "Title = \"Expansion\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n"),
+            ("balance/Odd Caster.balance.lua", "function SpellLevel(level)\n    return book.call(\"balance\", \"Full Caster\", \"SpellLevel\", level)\nend\n"),
+        ]));
+        assert_eq!(expansion.diagnostics().len(), 1);
+
+        let mut merged = Book::new();
+        merged.layer(&base);
+        merged.layer(&expansion);
+        assert!(
+            merged.diagnostics().is_empty(),
+            "{:?}",
+            merged.diagnostics()
+        );
+        assert_eq!(
+            merged
+                .balance
+                .find("Odd Caster")
+                .map(|balance| balance.spell_level(3)),
+            Some(3)
+        );
+
+        let broken = read_zip(zip_of(&[
+            (
+                "book.lua",
+                "Title = \"Broken\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n",
+            ),
+            (
+                "balance/Odd Caster.balance.lua",
+                "function SpellLevel(level)\n    return \"high\"\nend\n",
+            ),
+        ]));
+        merged.layer(&broken);
+        let paths: Vec<Box<str>> = merged
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| diagnostic.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![Box::from("Broken/balance/Odd Caster.balance.lua")]
+        );
+    }
+
+    #[test]
+    fn layered_books_round_trip_through_zip() {
+        let base = read_zip(zip_of(&[
+            ("book.lua", "Title = \"Base\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n"),
+            ("class/Tester.class.lua", "local ladder = require(\"ladder\")\n\nfunction Health(class_level)\n    return ladder(class_level)\nend\n"),
+            ("images/cover.png", "\u{89}PNG not really"),
+            ("lib/ladder.lua", "return function(x)\n    return x * 2\nend\n"),
+        ]));
+        let expansion = read_zip(zip_of(&[
+            (
+                "book.lua",
+                "Title = \"Expansion\"\nVersion = \"2.0.0\"\nSystemVersion = \"0.1.0\"\n",
+            ),
+            ("race/Human.race.lua", "Speed = 30\n"),
+        ]));
+        let mut merged = Book::new();
+        merged.layer(&base);
+        merged.layer(&expansion);
+
+        let written = merged.write_zip(io::Cursor::new(vec![])).unwrap();
+        let reread = read_zip(written.into_inner());
+        assert!(
+            reread.diagnostics().is_empty(),
+            "{:?}",
+            reread.diagnostics()
+        );
+        assert_eq!(reread.entries(), merged.entries());
+        assert_eq!(reread.title(), "Expansion");
+        assert_eq!(
+            reread.class.find("Tester").map(|class| class.health(3)),
+            Some(6)
+        );
+        assert!(reread.assets().contains_key("images/cover.png"));
+    }
+
+    #[test]
+    fn broken_sheets_are_saved_as_they_were_read() {
+        let book = read_zip(zip_of(&[
+            (
+                "book.lua",
+                "Title = \"Broken\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n",
+            ),
+            ("Bad.class.lua", "function Health(\n"),
+            ("Empty.class.lua", "  \n"),
+        ]));
+        assert_eq!(book.diagnostics().len(), 2, "{:?}", book.diagnostics());
+
+        let written = book.write_zip(io::Cursor::new(vec![])).unwrap();
+        let paths: Vec<String> = read_zip(written.into_inner())
+            .entries()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["book.lua", "class/Bad.class.lua", "class/Empty.class.lua"]
+        );
+        assert_eq!(book.entries()[1].1, b"function Health(\n");
+    }
+
+    #[test]
+    fn pages_outlive_their_book() {
+        let book = read_zip(zip_of(&[(
+            "class/Tester.class.lua",
+            "function Health(class_level)\n    return class_level * 3\nend\n",
+        )]));
+        let class = book.class.find("tester").unwrap();
+        drop(book);
+
+        let health = {
+            let class = class.clone();
+            move |level| class.health(level)
+        };
+        assert_eq!(class.name(), "Tester");
+        assert_eq!(health(2), 6);
+    }
+
+    #[test]
+    fn castable_spells_follow_astralic_types_and_spell_level() {
+        let book = read_zip(zip_of(&[
+            ("class/Pyro.class.lua", "AstralicTypes = { \"Fire\" }\n"),
+            (
+                "balance/Slow.balance.lua",
+                "function SpellLevel(class_level)\n    return math.floor(class_level / 2)\nend\n",
+            ),
+            (
+                "spell/Spark.spell.lua",
+                "Level = 0\nAstralicType = \"Fire\"\n",
+            ),
+            (
+                "spell/Fireball.spell.lua",
+                "Level = 2\nAstralicType = \"fire\"\n",
+            ),
+            (
+                "spell/Inferno.spell.lua",
+                "Level = 5\nAstralicType = \"Fire\"\n",
+            ),
+            (
+                "spell/Quake.spell.lua",
+                "Level = 1\nAstralicType = \"Earth\"\n",
+            ),
+            (
+                "spell/Misspelt.spell.lua",
+                "Level = \"nine\"\nAstralicType = \"Fire\"\n",
+            ),
+            (
+                "balance/Broken.balance.lua",
+                "function SpellLevel(class_level)\n    error(\"no table\")\nend\n",
+            ),
+        ]));
+        assert!(book.castable_spells("Pyro", "Broken", 10).is_empty());
+
+        for (class_level, expected) in [
+            // spell level 0 still casts cantrips
+            (1, vec!["Spark"]),
+            (2, vec!["Spark"]),
+            (4, vec!["Spark", "Fireball"]),
+            (10, vec!["Spark", "Fireball", "Inferno"]),
+        ] {
+            let spells: Vec<String> = book
+                .castable_spells("Pyro", "Slow", class_level)
+                .iter()
+                .map(|spell| spell.name().to_string())
+                .collect();
+            assert_eq!(spells, expected, "Failed for class level: {}", class_level);
+        }
+    }
+
+    #[test]
+    fn write_zip_moves_sheets_into_type_folders() {
+        let book = read_zip(zip_of(&[
+            ("Loose.class.lua", "Description = \"Not in a folder\"\n"),
+            (
+                "book.lua",
+                "Title = \"Loose\"\nVersion = \"1.0.0\"\nSystemVersion = \"0.1.0\"\n",
+            ),
+        ]));
+        let written = book.write_zip(io::Cursor::new(vec![])).unwrap();
+        let reread = read_zip(written.into_inner());
+        let paths: Vec<String> = reread.entries().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, ["book.lua", "class/Loose.class.lua"]);
+    }
+}
@@ -1,12 +1,24 @@
 use serde::{Deserialize, Serialize};
 
+use super::{
+    ability::{Abilities, AbilityName},
+    book::{Book, Page},
+    derive::{derive, Derived},
+    rules::{SavingThrowRule, Violation},
+};
+
+pub use super::character_file::CharacterFile;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Character {
     pub name: Box<str>,
     pub description: Box<str>,
     pub race: Race,
+    pub abilities: Abilities,
     pub classes: Vec<Class>,
     pub cybernetics: Vec<Cybernetic>,
+    /// What was picked for every choice a class or balance offered on levelling up.
+    pub choices: Vec<Choice>,
 }
 
 pub type CharacterLevel = usize;
@@ -15,6 +27,69 @@ impl Character {
     pub fn level(&self) -> CharacterLevel {
         self.classes.iter().map(|class| class.level).sum()
     }
+
+    /// Added to the rolls a character is proficient in, growing every four levels.
+    pub fn proficiency_bonus(&self) -> isize {
+        2 + self.level().saturating_sub(1) as isize / 4
+    }
+
+    /// A saving throw for every ability, proficient in those listed under `SavingThrows`
+    /// by the first class or, when the book's rules say so, by any class.
+    pub fn saving_throws(&self, book: &Book) -> Vec<SavingThrow> {
+        let granting = match book.rules().saving_throws {
+            SavingThrowRule::FirstClass => &self.classes[..self.classes.len().min(1)],
+            SavingThrowRule::EveryClass => &self.classes[..],
+        };
+        let proficient: Vec<AbilityName> = granting
+            .iter()
+            .filter_map(|class| book.class.find(&class.name))
+            .flat_map(|class| class.saving_throws())
+            .filter_map(|name| AbilityName::try_from(name.as_ref()).ok())
+            .collect();
+        self.abilities
+            .iter()
+            .map(|(name, ability)| {
+                let proficient = proficient.contains(&name);
+                SavingThrow {
+                    ability: name,
+                    proficient,
+                    bonus: ability.modifier()
+                        + if proficient {
+                            self.proficiency_bonus()
+                        } else {
+                            0
+                        },
+                }
+            })
+            .collect()
+    }
+
+    /// Modifier of the ability an Astralic type is cast with, `None` when the book has no
+    /// such type or it names no known ability.
+    pub fn casting_modifier(&self, book: &Book, astralic_type: &str) -> Option<isize> {
+        let astralic = book.astralics.find(astralic_type)?;
+        let name = AbilityName::try_from(astralic.casting_ability().as_ref()).ok()?;
+        Some(self.abilities.get(name).modifier())
+    }
+
+    /// Health, Armor Rating, spell level, skills and cybernetics from the class and
+    /// balance sheets of `book`.
+    pub fn derive(&self, book: &Book) -> Derived {
+        derive(self, book)
+    }
+
+    /// Everything about the character the rules of `book` don't allow.
+    pub fn violations(&self, book: &Book) -> Vec<Violation> {
+        book.rules().check(self, book)
+    }
+}
+
+/// The bonus to a saving throw of one ability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SavingThrow {
+    pub ability: AbilityName,
+    pub proficient: bool,
+    pub bonus: isize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -30,7 +105,7 @@ pub struct Race {
 
 pub type ClassLevel = usize;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Class {
     pub name: Box<str>,
     pub balance: Box<str>,
@@ -41,3 +116,44 @@ pub struct Class {
 pub struct Cybernetic {
     pub name: Box<str>,
 }
+
+/// One pick from the `Choices` of a class or balance sheet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    /// The class whose level offered the choice, also for choices its balance offered.
+    pub class: Box<str>,
+    pub level: ClassLevel,
+    pub name: Box<str>,
+    pub picked: Box<str>,
+}
+
+#[cfg(test)]
+impl Character {
+    /// A human with default abilities and nothing but `classes`, each a name, balance
+    /// and level.
+    pub(super) fn test_with_classes(classes: &[(&str, &str, ClassLevel)]) -> Self {
+        Self {
+            name: "Vex".into(),
+            description: "".into(),
+            race: Race {
+                name: "Human".into(),
+                info: "".into(),
+                age: "".into(),
+                size: "".into(),
+                speed: 30,
+                languages: vec![],
+            },
+            abilities: Abilities::default(),
+            classes: classes
+                .iter()
+                .map(|(name, balance, level)| Class {
+                    name: (*name).into(),
+                    balance: (*balance).into(),
+                    level: *level,
+                })
+                .collect(),
+            cybernetics: vec![],
+            choices: vec![],
+        }
+    }
+}
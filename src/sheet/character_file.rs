@@ -0,0 +1,309 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::{ability::Abilities, book::Book, character::Character, shelf::Shelf};
+
+/// Version of the `.character.json` format this build reads and writes.
+pub const FORMAT_VERSION: u64 = MIGRATIONS.len() as u64;
+
+/// Each entry upgrades a file from the format version it sits at to the next one.
+/// Files are taken through every later step in order, so old files keep opening.
+const MIGRATIONS: &[Migration] = &[wrap_bare_character, add_abilities, add_choices];
+
+/// Upgrades a file by one format version, failing when it isn't shaped like that version.
+type Migration = fn(Value) -> Result<Value, CharacterFileError>;
+
+/// `value` as an object, or an error naming `what` it should have been.
+fn object<'v>(
+    value: Option<&'v mut Value>,
+    what: &'static str,
+) -> Result<&'v mut Map<String, Value>, CharacterFileError> {
+    value
+        .and_then(Value::as_object_mut)
+        .ok_or(CharacterFileError::NotAnObject(what))
+}
+
+/// The character of a migrated file, which has to be an object like the file itself.
+fn character(file: &mut Value) -> Result<&mut Map<String, Value>, CharacterFileError> {
+    let file = object(Some(file), "the file")?;
+    object(file.get_mut("character"), "character")
+}
+
+/// Version 0 was a bare `Character`, as serde wrote it before the format had a version.
+fn wrap_bare_character(character: Value) -> Result<Value, CharacterFileError> {
+    if !character.is_object() {
+        return Err(CharacterFileError::NotAnObject("the file"));
+    }
+    Ok(json!({
+        "format_version": 1,
+        "books": [],
+        "character": character,
+    }))
+}
+
+/// Version 1 had no ability scores, characters start with the default in each.
+fn add_abilities(mut file: Value) -> Result<Value, CharacterFileError> {
+    character(&mut file)?.insert("abilities".into(), json!(Abilities::default()));
+    file["format_version"] = json!(2);
+    Ok(file)
+}
+
+/// Version 2 kept no choices, characters made before it picked nothing.
+fn add_choices(mut file: Value) -> Result<Value, CharacterFileError> {
+    character(&mut file)?.insert("choices".into(), json!([]));
+    file["format_version"] = json!(3);
+    Ok(file)
+}
+
+/// A book a character was built against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BookRef {
+    pub title: Box<str>,
+    /// Version from the book's manifest, empty when it has none.
+    pub version: Box<str>,
+}
+
+impl BookRef {
+    pub fn of(book: &Book) -> Self {
+        Self {
+            title: book.title().into(),
+            version: book
+                .manifest()
+                .map(|manifest| manifest.version.to_string())
+                .unwrap_or_default()
+                .into(),
+        }
+    }
+}
+
+/// A `.character.json` file: a character and the books it was built against.
+///
+/// ```json
+/// {
+///   "format_version": 3,
+///   "books": [{ "title": "Cybernetic TRPG", "version": "1.0.0" }],
+///   "character": { "name": "Vex", ... }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CharacterFile {
+    format_version: u64,
+    /// The books of the shelf the character was last saved with, bottom to top.
+    pub books: Vec<BookRef>,
+    pub character: Character,
+}
+
+impl CharacterFile {
+    pub fn new(character: Character, shelf: &Shelf) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            books: shelf.books().iter().map(BookRef::of).collect(),
+            character,
+        }
+    }
+
+    /// Reads a file of any format version up to `FORMAT_VERSION`, migrating older ones.
+    pub fn from_json(json: &str) -> Result<Self, CharacterFileError> {
+        let mut value: Value = serde_json::from_str(json)?;
+        let version = match value.get("format_version") {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| CharacterFileError::Version(version.to_string()))?,
+        };
+        if version > FORMAT_VERSION {
+            return Err(CharacterFileError::Newer(version));
+        }
+        for migrate in &MIGRATIONS[version as usize..] {
+            value = migrate(value)?;
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, CharacterFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn open(path: &Path) -> Result<Self, CharacterFileError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Writes the character, recording the books of `shelf` as the ones it was built against.
+    pub fn save(&mut self, path: &Path, shelf: &Shelf) -> Result<(), CharacterFileError> {
+        self.books = shelf.books().iter().map(BookRef::of).collect();
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Books the character was built against that `shelf` lacks or holds at another version.
+    pub fn book_changes(&self, shelf: &Shelf) -> Vec<String> {
+        let shelved: Vec<BookRef> = shelf.books().iter().map(BookRef::of).collect();
+        self.books
+            .iter()
+            .filter_map(
+                |book| match shelved.iter().find(|shelved| shelved.title == book.title) {
+                    None => Some(format!(
+                        "built with \"{}\" {}, which isn't open",
+                        book.title, book.version
+                    )),
+                    Some(shelved) if shelved.version != book.version => Some(format!(
+                        "built with \"{}\" {}, the open one is {}",
+                        book.title, book.version, shelved.version
+                    )),
+                    Some(_) => None,
+                },
+            )
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum CharacterFileError {
+    IO(io::Error),
+    Json(serde_json::Error),
+    /// `format_version` isn't a whole number.
+    Version(String),
+    /// The file was written by a newer build, with this format version.
+    Newer(u64),
+    /// The file, or its character, isn't a JSON object.
+    NotAnObject(&'static str),
+}
+
+impl std::error::Error for CharacterFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CharacterFileError::IO(error) => error.source(),
+            CharacterFileError::Json(error) => error.source(),
+            CharacterFileError::Version(_)
+            | CharacterFileError::Newer(_)
+            | CharacterFileError::NotAnObject(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for CharacterFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CharacterFileError::IO(error) => error.fmt(f),
+            CharacterFileError::Json(error) => error.fmt(f),
+            CharacterFileError::Version(version) => {
+                write!(
+                    f,
+                    "format_version should be a whole number, found {}",
+                    version
+                )
+            }
+            CharacterFileError::Newer(version) => write!(
+                f,
+                "character file has format version {}, this app reads up to {}",
+                version, FORMAT_VERSION
+            ),
+            CharacterFileError::NotAnObject(what) => {
+                write!(f, "{} should be a JSON object", what)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for CharacterFileError {
+    fn from(value: io::Error) -> Self {
+        CharacterFileError::IO(value)
+    }
+}
+
+impl From<serde_json::Error> for CharacterFileError {
+    fn from(value: serde_json::Error) -> Self {
+        CharacterFileError::Json(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ability::Ability;
+    use super::*;
+
+    const BARE: &str = r#"{
+        "name": "Vex",
+        "description": "",
+        "race": {
+            "name": "Human",
+            "info": "",
+            "age": "30",
+            "size": "Medium",
+            "speed": 30,
+            "languages": ["Common"]
+        },
+        "classes": [{ "name": "Shadowjack", "balance": "Full Martial", "level": 3 }],
+        "cybernetics": []
+    }"#;
+
+    #[test]
+    fn bare_characters_migrate_to_the_current_format() {
+        let file = CharacterFile::from_json(BARE).unwrap();
+        assert_eq!(file.format_version, FORMAT_VERSION);
+        assert!(file.books.is_empty());
+        assert_eq!(&*file.character.name, "Vex");
+        assert_eq!(file.character.level(), 3);
+        assert_eq!(file.character.abilities.grit.score(), 10);
+        assert!(file.character.choices.is_empty());
+
+        let reread = CharacterFile::from_json(&file.to_json().unwrap()).unwrap();
+        assert_eq!(reread.format_version, FORMAT_VERSION);
+        assert_eq!(&*reread.character.classes[0].name, "Shadowjack");
+    }
+
+    #[test]
+    fn ability_scores_are_clamped_when_read() {
+        let file = format!(
+            "{{\"format_version\": {}, \"books\": [], \"character\": {}}}",
+            FORMAT_VERSION,
+            BARE.replace(
+                "\"cybernetics\": []",
+                "\"cybernetics\": [], \"choices\": [], \"abilities\": { \"grit\": 99, \"astralic\": -4 }"
+            )
+        );
+        let file = CharacterFile::from_json(&file).unwrap();
+        let abilities = file.character.abilities;
+        assert_eq!(abilities.grit.score(), Ability::MAX_SCORE);
+        assert_eq!(abilities.astralic.score(), Ability::MIN_SCORE);
+        assert_eq!(abilities.kinetics.score(), 10);
+    }
+
+    #[test]
+    fn newer_and_broken_versions_are_refused() {
+        let newer = format!(
+            "{{\"format_version\": {}, \"books\": [], \"character\": {}}}",
+            FORMAT_VERSION + 1,
+            BARE
+        );
+        assert!(matches!(
+            CharacterFile::from_json(&newer),
+            Err(CharacterFileError::Newer(_))
+        ));
+        assert!(matches!(
+            CharacterFile::from_json("{\"format_version\": \"one\"}"),
+            Err(CharacterFileError::Version(_))
+        ));
+    }
+
+    #[test]
+    fn files_that_are_not_objects_are_refused() {
+        for json in [
+            "[]",
+            "5",
+            "\"x\"",
+            "{\"format_version\": 1, \"character\": 3}",
+            "{\"format_version\": 2, \"books\": [], \"character\": []}",
+        ] {
+            let result = CharacterFile::from_json(json);
+            assert!(
+                matches!(result, Err(CharacterFileError::NotAnObject(_))),
+                "Failed for file: {}\n{:?}",
+                json,
+                result
+            );
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use super::{
+    ability::AbilityName,
+    book::{Book, Page, PageError},
+    character::{Character, ClassLevel},
+    rules::SpellLevelRule,
+};
+
+/// What part of a character a derived value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Class {
+        name: Box<str>,
+        level: ClassLevel,
+    },
+    /// The balance a class was taken with.
+    Balance {
+        name: Box<str>,
+        class: Box<str>,
+        level: ClassLevel,
+    },
+    /// The ability's modifier.
+    Ability(AbilityName),
+    /// Picked for the character rather than granted by a sheet.
+    Chosen,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Class { name, level } => write!(f, "{} {}", name, level),
+            Source::Balance { name, class, level } => write!(f, "{} ({} {})", name, class, level),
+            Source::Ability(ability) => write!(f, "{} modifier", ability),
+            Source::Chosen => f.write_str("chosen"),
+        }
+    }
+}
+
+/// A derived number and what it was made of.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stat {
+    pub total: isize,
+    pub parts: Vec<(Source, isize)>,
+}
+
+impl Stat {
+    fn sum(parts: Vec<(Source, isize)>) -> Self {
+        Self {
+            total: parts.iter().map(|(_, value)| value).sum(),
+            parts,
+        }
+    }
+
+    fn max(parts: Vec<(Source, isize)>) -> Self {
+        Self {
+            total: parts
+                .iter()
+                .map(|(_, value)| *value)
+                .max()
+                .unwrap_or_default(),
+            parts,
+        }
+    }
+}
+
+/// Names a character was granted, such as skills, and which source granted which.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Granted {
+    pub names: Vec<Box<str>>,
+    pub parts: Vec<(Source, Vec<Box<str>>)>,
+}
+
+/// A character's numbers as its classes and balances in a book make them.
+#[derive(Debug, Clone, Default)]
+pub struct Derived {
+    /// Every class and balance, plus the Grit modifier for each character level.
+    pub health: Stat,
+    /// Every class and balance added up.
+    pub armor_rating: Stat,
+    /// The highest any class or balance reaches, or all of them added up when the
+    /// book's rules say so.
+    pub spell_level: Stat,
+    /// Every skill granted, a skill granted twice counts twice.
+    pub skills: Granted,
+    /// Every cybernetic granted or chosen, each once.
+    pub cybernetics: Granted,
+    /// Values a sheet failed to give, so the numbers above may be short.
+    pub errors: Vec<(Source, PageError)>,
+    /// Classes and balances of the character the book has no sheet for.
+    pub missing: Vec<Source>,
+}
+
+/// Numbers one sheet gives at a class level.
+struct Contribution {
+    source: Source,
+    health: Option<usize>,
+    armor_rating: Option<usize>,
+    spell_level: Option<usize>,
+    skills: Vec<Box<str>>,
+    cybernetics: Vec<Box<str>>,
+}
+
+/// Asks a class or balance sheet for everything it gives at `level`, keeping what
+/// fails in `errors` instead of reading it as zero.
+fn contribution(
+    page: &impl Page,
+    source: Source,
+    level: ClassLevel,
+    errors: &mut Vec<(Source, PageError)>,
+) -> Contribution {
+    fn keep<T>(
+        value: Result<Option<T>, PageError>,
+        source: &Source,
+        errors: &mut Vec<(Source, PageError)>,
+    ) -> Option<T> {
+        value
+            .map_err(|err| errors.push((source.clone(), err)))
+            .ok()
+            .flatten()
+    }
+    let health = keep(page.try_health(level), &source, errors);
+    let armor_rating = keep(page.try_armor_rating(level), &source, errors);
+    let spell_level = keep(page.try_spell_level(level), &source, errors);
+    let skills = keep(page.try_skills(level), &source, errors).unwrap_or_default();
+    let cybernetics = keep(page.try_cybernetics(level), &source, errors).unwrap_or_default();
+    Contribution {
+        source,
+        health,
+        armor_rating,
+        spell_level,
+        skills,
+        cybernetics,
+    }
+}
+
+/// Works out a character's Health, Armor Rating, spell level, skills and cybernetics
+/// from the class and balance sheets of `book`, each at the level of its class.
+pub fn derive(character: &Character, book: &Book) -> Derived {
+    let mut derived = Derived::default();
+    let mut contributions = vec![];
+    for class in character.classes.iter() {
+        let source = Source::Class {
+            name: class.name.clone(),
+            level: class.level,
+        };
+        match book.class.find(&class.name) {
+            Some(sheet) => contributions.push(contribution(
+                &sheet,
+                source,
+                class.level,
+                &mut derived.errors,
+            )),
+            None => derived.missing.push(source),
+        }
+
+        let source = Source::Balance {
+            name: class.balance.clone(),
+            class: class.name.clone(),
+            level: class.level,
+        };
+        match book.balance.find(&class.balance) {
+            Some(sheet) => contributions.push(contribution(
+                &sheet,
+                source,
+                class.level,
+                &mut derived.errors,
+            )),
+            None => derived.missing.push(source),
+        }
+    }
+
+    let parts = |value: fn(&Contribution) -> Option<usize>| -> Vec<(Source, isize)> {
+        contributions
+            .iter()
+            .filter_map(|contribution| {
+                value(contribution).map(|value| (contribution.source.clone(), value as isize))
+            })
+            .collect()
+    };
+
+    let mut health = parts(|contribution| contribution.health);
+    let grit = character.abilities.grit.modifier() * character.level() as isize;
+    if grit != 0 {
+        health.push((Source::Ability(AbilityName::Grit), grit));
+    }
+    derived.health = Stat::sum(health);
+    derived.armor_rating = Stat::sum(parts(|contribution| contribution.armor_rating));
+    let spell_levels = parts(|contribution| contribution.spell_level);
+    derived.spell_level = match book.rules().spell_levels {
+        SpellLevelRule::Highest => Stat::max(spell_levels),
+        SpellLevelRule::Sum => Stat::sum(spell_levels),
+    };
+
+    for contribution in contributions.iter() {
+        if !contribution.skills.is_empty() {
+            derived
+                .skills
+                .names
+                .extend(contribution.skills.iter().cloned());
+            derived
+                .skills
+                .parts
+                .push((contribution.source.clone(), contribution.skills.clone()));
+        }
+    }
+
+    let chosen = (
+        Source::Chosen,
+        character
+            .cybernetics
+            .iter()
+            .map(|cybernetic| cybernetic.name.clone())
+            .collect::<Vec<_>>(),
+    );
+    let granted = contributions
+        .into_iter()
+        .map(|contribution| (contribution.source, contribution.cybernetics));
+    for (source, names) in granted.chain([chosen]) {
+        if names.is_empty() {
+            continue;
+        }
+        for name in names.iter() {
+            if !derived.cybernetics.names.contains(name) {
+                derived.cybernetics.names.push(name.clone());
+            }
+        }
+        derived.cybernetics.parts.push((source, names));
+    }
+
+    derived
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ability::Ability, book::Src, character::Cybernetic};
+    use super::*;
+
+    fn book() -> Book {
+        let mut book = Book::new();
+        book.class.write(
+            "Pyro".into(),
+            Src::load(
+                "Pyro",
+                "function Health(level)\n    return level * 4\nend\nfunction Skills(level)\n    return { \"Spark\" }\nend\nfunction Cybernetics(level)\n    return { \"Ember Core\" }\nend\n",
+            )
+            .unwrap(),
+        );
+        book.class.write(
+            "Brute".into(),
+            Src::load(
+                "Brute",
+                "function Health(level)\n    return level * 10\nend\nfunction ArmorRating(level)\n    error(\"no armor table\")\nend\n",
+            )
+            .unwrap(),
+        );
+        book.balance.write(
+            "Caster".into(),
+            Src::load(
+                "Caster",
+                "function Health(level)\n    return level * 6\nend\nfunction SpellLevel(level)\n    return math.ceil(level / 2)\nend\n",
+            )
+            .unwrap(),
+        );
+        book
+    }
+
+    fn character(classes: &[(&str, &str, ClassLevel)]) -> Character {
+        let mut character = Character::test_with_classes(classes);
+        character.abilities.grit = Ability::from_score(14);
+        character.cybernetics.push(Cybernetic {
+            name: "Ember Core".into(),
+        });
+        character
+    }
+
+    #[test]
+    fn derive_adds_up_every_class_with_a_breakdown() {
+        let derived = derive(
+            &character(&[("Pyro", "Caster", 3), ("Brute", "Caster", 2)]),
+            &book(),
+        );
+
+        // Pyro 12 + Caster 18 + Brute 20 + Caster 12 + Grit 2 * 5
+        assert_eq!(derived.health.total, 72);
+        assert_eq!(derived.health.parts.len(), 5);
+        assert_eq!(
+            derived.health.parts[0],
+            (
+                Source::Class {
+                    name: "Pyro".into(),
+                    level: 3
+                },
+                12
+            )
+        );
+        assert_eq!(derived.spell_level.total, 2);
+        assert_eq!(derived.skills.names, vec![Box::from("Spark")]);
+        assert_eq!(derived.cybernetics.names, vec![Box::from("Ember Core")]);
+        assert_eq!(derived.cybernetics.parts.len(), 2);
+
+        assert_eq!(derived.errors.len(), 1);
+        assert_eq!(derived.errors[0].0.to_string(), "Brute 2");
+        assert!(derived.missing.is_empty());
+
+        let derived = derive(&character(&[("Pyro", "Martial", 1)]), &book());
+        assert_eq!(derived.missing.len(), 1);
+        assert_eq!(derived.health.total, 6);
+    }
+}
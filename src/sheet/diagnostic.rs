@@ -0,0 +1,188 @@
+use super::{book::ContentType, character::ClassLevel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+            Severity::Info => f.write_str("info"),
+        }
+    }
+}
+
+/// Position inside a Lua source reported by Luau.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LuaLocation {
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
+impl LuaLocation {
+    /// Finds the first `:<line>:` or `:<line>:<column>:` in a Luau error message.
+    pub fn parse(message: &str) -> Option<Self> {
+        let mut rest = message;
+        while let Some(index) = rest.find(':') {
+            rest = &rest[index + 1..];
+            if let Some((line, after)) = leading_number(rest) {
+                let column = leading_number(after).map(|(column, _)| column);
+                return Some(Self { line, column });
+            }
+        }
+        None
+    }
+}
+
+/// Reads `<digits>:` from the start of `text`, returning the number and what follows the colon.
+fn leading_number(text: &str) -> Option<(usize, &str)> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || !text[digits..].starts_with(':') {
+        return None;
+    }
+    Some((text[..digits].parse().ok()?, &text[digits + 1..]))
+}
+
+impl std::fmt::Display for LuaLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "{}:{}", self.line, column),
+            None => write!(f, "{}", self.line),
+        }
+    }
+}
+
+/// Something that went wrong, or looks suspicious, while reading a book.
+#[derive(Debug, Clone)]
+pub struct BookDiagnostic {
+    /// Path of the entry inside the book.
+    pub path: Box<str>,
+    pub content_type: Option<ContentType>,
+    /// Name of the sheet the entry would have produced.
+    pub sheet: Option<Box<str>>,
+    /// Class level the sheet was called with when the problem showed up.
+    pub level: Option<ClassLevel>,
+    pub severity: Severity,
+    pub message: Box<str>,
+    pub location: Option<LuaLocation>,
+    /// Names the author probably meant.
+    pub suggestions: Vec<Box<str>>,
+}
+
+impl BookDiagnostic {
+    pub fn new(
+        severity: Severity,
+        path: impl Into<Box<str>>,
+        message: impl Into<Box<str>>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            content_type: None,
+            sheet: None,
+            level: None,
+            severity,
+            message: message.into(),
+            location: None,
+            suggestions: vec![],
+        }
+    }
+
+    pub fn error(path: impl Into<Box<str>>, message: impl Into<Box<str>>) -> Self {
+        Self::new(Severity::Error, path, message)
+    }
+
+    pub fn warning(path: impl Into<Box<str>>, message: impl Into<Box<str>>) -> Self {
+        Self::new(Severity::Warning, path, message)
+    }
+
+    pub fn info(path: impl Into<Box<str>>, message: impl Into<Box<str>>) -> Self {
+        Self::new(Severity::Info, path, message)
+    }
+
+    /// Creates an error from a Luau failure, keeping the line and column it points at.
+    pub fn lua(path: impl Into<Box<str>>, err: &mlua::Error) -> Self {
+        let message = err.to_string();
+        let location = LuaLocation::parse(&message);
+        Self {
+            location,
+            ..Self::error(path, message)
+        }
+    }
+
+    pub fn with_sheet(mut self, content_type: ContentType, sheet: impl Into<Box<str>>) -> Self {
+        self.content_type = Some(content_type);
+        self.sheet = Some(sheet.into());
+        self
+    }
+
+    pub fn with_level(mut self, level: ClassLevel) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<Box<str>>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+}
+
+impl std::fmt::Display for BookDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.path)?;
+        if let Some(location) = self.location {
+            write!(f, ":{}", location)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(level) = self.level {
+            write!(f, " (class level {})", level)?;
+        }
+        if !self.suggestions.is_empty() {
+            let suggestions = self
+                .suggestions
+                .iter()
+                .map(|suggestion| format!("\"{}\"", suggestion))
+                .collect::<Vec<_>>();
+            write!(f, ", did you mean {}?", suggestions.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lua_location_from_message() {
+        for (message, expected) in [
+            (
+                "class/Shadowjack.class.lua:12: Expected identifier",
+                Some(LuaLocation {
+                    line: 12,
+                    column: None,
+                }),
+            ),
+            (
+                "[string \"Spirit Brewer.class.lua\"]:3:7: Incomplete statement",
+                Some(LuaLocation {
+                    line: 3,
+                    column: Some(7),
+                }),
+            ),
+            ("runtime error: attempt to call a nil value", None),
+            ("C:/books/odd: name: 4", None),
+        ] {
+            assert_eq!(
+                LuaLocation::parse(message),
+                expected,
+                "Failed for message: {}",
+                message
+            );
+        }
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use mlua::{Lua, MultiValue, Table, Value, Variadic};
+
+use super::{
+    book::{ContentType, Src},
+    limits::Limits,
+    lint::{normalize, suggestions},
+    vm::Vm,
+};
+
+/// Name of the read-only global every sheet can use to look up the other sheets of its book.
+///
+/// ```lua
+/// book.exists("skill", "Drunken Reflexes")          -- true or false
+/// book.names("balance")                             -- { "Full Caster", "Half Caster", ... }
+/// book.get("balance", "Half Caster", "Description") -- a global of another sheet
+/// book.call("balance", "Half Caster", "SpellLevel", class_level)
+/// ```
+pub const MODULE: &str = "book";
+
+/// Folder of a book holding the modules its sheets can `require`.
+pub const LIB_DIR: &str = "lib";
+
+/// Where the module sheets `require` by `name` lives in a book.
+pub fn module_path(name: &str) -> String {
+    format!("{}/{}.lua", LIB_DIR, name)
+}
+
+/// Registry key of the `require` every sheet of a VM shares.
+const REQUIRE: &str = "book_require";
+
+/// How deep tables passed between sheets may nest.
+const MAX_DEPTH: usize = 32;
+
+type SheetId = (ContentType, Box<str>);
+
+#[derive(Debug, Default)]
+struct State {
+    sheets: HashMap<SheetId, Src>,
+    /// Sources of the book's `lib` modules, by the name sheets `require` them with.
+    libs: HashMap<Box<str>, Rc<str>>,
+    /// Sheets with a `book.call` into them still running, outermost first.
+    calls: Vec<SheetId>,
+    /// Set while a sheet's top level code runs, when the rest of the book isn't there yet.
+    loading: bool,
+    /// The host of the book this one was layered into, lookups go there instead.
+    layered: Option<Host>,
+    limits: Limits,
+    /// The VM every sheet of the book is loaded into, made when the first one loads.
+    vm: Option<Vm>,
+}
+
+/// The sheets of one book as their Lua sees them through the `book` module.
+#[derive(Debug, Clone, Default)]
+pub struct Host(Rc<RefCell<State>>);
+
+impl Host {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        let host = Self::default();
+        host.0.borrow_mut().limits = limits;
+        host
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.0.borrow().limits
+    }
+
+    /// Runs a sheet's top level code with the `book` module installed, bounded by the
+    /// host's limits.
+    pub fn load(
+        &self,
+        content_type: ContentType,
+        name: &str,
+        path: &str,
+        source: String,
+    ) -> mlua::Result<Src> {
+        let caller = (content_type, Box::from(name));
+        let vm = self.vm()?;
+        self.0.borrow_mut().loading = true;
+        let src = vm.load(path, source, |lua, env| install(lua, env, &self.0, caller));
+        self.0.borrow_mut().loading = false;
+        src
+    }
+
+    fn vm(&self) -> mlua::Result<Vm> {
+        if let Some(vm) = &self.0.borrow().vm {
+            return Ok(vm.clone());
+        }
+        let vm = Vm::new(self.limits())?;
+        vm.lua()
+            .set_named_registry_value(REQUIRE, require(vm.lua(), Rc::downgrade(&self.0))?)?;
+        self.0.borrow_mut().vm = Some(vm.clone());
+        Ok(vm)
+    }
+
+    /// Replaces the sheets lookups can find.
+    pub fn set_sheets(&self, sheets: impl IntoIterator<Item = (ContentType, Box<str>, Src)>) {
+        self.0.borrow_mut().sheets = sheets
+            .into_iter()
+            .map(|(content_type, name, src)| ((content_type, name), src))
+            .collect();
+    }
+
+    /// Replaces the modules sheets can `require`.
+    pub fn set_libs(&self, libs: impl IntoIterator<Item = (Box<str>, Rc<str>)>) {
+        self.0.borrow_mut().libs = libs.into_iter().collect();
+    }
+
+    /// Sends every lookup of this book's sheets to `other`, the book they were layered into,
+    /// so a sheet of an expansion can find the sheets of the books beneath it.
+    pub fn layer_into(&self, other: &Host) {
+        if Rc::ptr_eq(&self.0, &other.0) {
+            return;
+        }
+        self.0.borrow_mut().layered = Some(other.clone());
+    }
+
+    /// The host lookups of this book's sheets end up at.
+    fn target(&self) -> Host {
+        let layered = self.0.borrow().layered.clone();
+        match layered {
+            Some(host) => host.target(),
+            None => self.clone(),
+        }
+    }
+
+    /// Finds a sheet by name, forgiving the same differences `Section::find` does.
+    fn find(&self, content_type: ContentType, name: &str) -> Result<(SheetId, Src), String> {
+        let state = self.0.borrow();
+        let id = (content_type, Box::from(name));
+        if let Some(src) = state.sheets.get(&id) {
+            return Ok((id, src.clone()));
+        }
+
+        let wanted = normalize(name);
+        let found = state
+            .sheets
+            .iter()
+            .find(|((candidate_type, candidate), _)| {
+                *candidate_type == content_type && normalize(candidate) == wanted
+            });
+        if let Some((id, src)) = found {
+            return Ok((id.clone(), src.clone()));
+        }
+
+        let known = state
+            .sheets
+            .keys()
+            .filter(|(candidate_type, _)| *candidate_type == content_type)
+            .map(|(_, candidate)| candidate.as_ref());
+        let mut message = format!("book has no {} named \"{}\"", content_type.as_str(), name);
+        if let Some(suggestion) = suggestions(name, known).first() {
+            message.push_str(&format!(", did you mean \"{}\"?", suggestion));
+        }
+        Err(message)
+    }
+
+    fn names(&self, content_type: ContentType) -> Vec<Box<str>> {
+        let mut names: Vec<Box<str>> = self
+            .0
+            .borrow()
+            .sheets
+            .keys()
+            .filter(|(candidate_type, _)| *candidate_type == content_type)
+            .map(|(_, name)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Marks `callee` as running until the returned guard drops, failing if it already is.
+    fn enter(&self, caller: &SheetId, callee: SheetId) -> Result<Call, String> {
+        let mut state = self.0.borrow_mut();
+        let mut pushed = 0;
+        if state.calls.is_empty() {
+            state.calls.push(caller.clone());
+            pushed += 1;
+        }
+
+        if let Some(start) = state.calls.iter().position(|id| *id == callee) {
+            let cycle = state.calls[start..]
+                .iter()
+                .chain([&callee])
+                .map(|(content_type, name)| format!("{} \"{}\"", content_type.as_str(), name))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            let len = state.calls.len();
+            state.calls.truncate(len - pushed);
+            return Err(format!("sheets call each other in a cycle: {}", cycle));
+        }
+
+        state.calls.push(callee);
+        pushed += 1;
+        Ok(Call {
+            host: self.clone(),
+            pushed,
+        })
+    }
+}
+
+/// A `book.call` in progress.
+struct Call {
+    host: Host,
+    pushed: usize,
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        let mut state = self.host.0.borrow_mut();
+        let len = state.calls.len();
+        state.calls.truncate(len.saturating_sub(self.pushed));
+    }
+}
+
+/// The host behind a sheet's `book` module, as long as its book is still around and done loading.
+fn host_of(state: &Weak<RefCell<State>>) -> mlua::Result<Host> {
+    let state = match state.upgrade() {
+        Some(state) => state,
+        None => return Err(mlua::Error::runtime("the book of this sheet was closed")),
+    };
+    if state.borrow().loading {
+        return Err(mlua::Error::runtime(
+            "other sheets can't be looked up while a sheet loads, use book inside a function",
+        ));
+    }
+    Ok(Host(state).target())
+}
+
+fn content_type_of(content_type: &str) -> mlua::Result<ContentType> {
+    ContentType::try_from(content_type)
+        .map_err(|_| mlua::Error::runtime(format!("unknown content type \"{}\"", content_type)))
+}
+
+/// Sets the `book` and `require` globals of a sheet's environment before it loads.
+fn install(
+    lua: &Lua,
+    env: &Table,
+    state: &Rc<RefCell<State>>,
+    caller: SheetId,
+) -> mlua::Result<()> {
+    env.raw_set(
+        "require",
+        lua.named_registry_value::<mlua::Function>(REQUIRE)?,
+    )?;
+
+    let module = lua.create_table()?;
+
+    // the module holds the book weakly, as the book holds the Lua
+    let weak = Rc::downgrade(state);
+    module.set(
+        "exists",
+        lua.create_function(move |_, (content_type, name): (String, String)| {
+            let host = host_of(&weak)?;
+            Ok(host.find(content_type_of(&content_type)?, &name).is_ok())
+        })?,
+    )?;
+
+    let weak = Rc::downgrade(state);
+    module.set(
+        "names",
+        lua.create_function(move |_, content_type: String| {
+            let host = host_of(&weak)?;
+            Ok(host.names(content_type_of(&content_type)?))
+        })?,
+    )?;
+
+    let weak = Rc::downgrade(state);
+    module.set(
+        "get",
+        lua.create_function(
+            move |lua, (content_type, name, key): (String, String, String)| {
+                let host = host_of(&weak)?;
+                let (_, src) = host
+                    .find(content_type_of(&content_type)?, &name)
+                    .map_err(mlua::Error::runtime)?;
+                transfer(src.get::<Value>(key)?, lua, MAX_DEPTH)
+            },
+        )?,
+    )?;
+
+    let weak = Rc::downgrade(state);
+    module.set(
+        "call",
+        lua.create_function(
+            move |lua,
+                  (content_type, name, key, args): (
+                String,
+                String,
+                String,
+                Variadic<Value>,
+            )| {
+                let host = host_of(&weak)?;
+                let (callee, src) = host
+                    .find(content_type_of(&content_type)?, &name)
+                    .map_err(mlua::Error::runtime)?;
+                let _call = host
+                    .enter(&caller, callee)
+                    .map_err(mlua::Error::runtime)?;
+
+                let args = args
+                    .into_iter()
+                    .map(|arg| transfer(arg, src.src(), MAX_DEPTH))
+                    .collect::<mlua::Result<MultiValue>>()?;
+                let results: MultiValue = src.call(key, args)?;
+                results
+                    .into_iter()
+                    .map(|result| transfer(result, lua, MAX_DEPTH))
+                    .collect::<mlua::Result<MultiValue>>()
+            },
+        )?,
+    )?;
+
+    module.set_readonly(true);
+    env.raw_set(MODULE, module)
+}
+
+/// Replaces Luau's `require`, which reads the file system, with one that only finds the
+/// modules in the `lib` folder of the sheet's own book. A module runs once per book, in
+/// an environment of its own, and the table it returns is read-only as sheets share it.
+fn require(lua: &Lua, state: Weak<RefCell<State>>) -> mlua::Result<mlua::Function> {
+    let loaded = lua.create_table()?;
+    let requiring: RefCell<Vec<Box<str>>> = RefCell::new(vec![]);
+    lua.create_function(move |lua, name: String| {
+        let name = name.trim_end_matches(".lua");
+        let name: Box<str> = name
+            .strip_prefix(&format!("{}/", LIB_DIR))
+            .unwrap_or(name)
+            .into();
+
+        let value: Value = loaded.raw_get(&*name)?;
+        if !value.is_nil() {
+            return Ok(value);
+        }
+
+        if let Some(start) = requiring.borrow().iter().position(|other| *other == name) {
+            let cycle = requiring.borrow()[start..]
+                .iter()
+                .chain([&name])
+                .map(|name| format!("\"{}\"", name))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(mlua::Error::runtime(format!(
+                "modules require each other in a cycle: {}",
+                cycle
+            )));
+        }
+
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return Err(mlua::Error::runtime("the book of this sheet was closed")),
+        };
+        let source = state.borrow().libs.get(&name).cloned();
+        let source = match source {
+            Some(source) => source,
+            None => {
+                let state = state.borrow();
+                let mut message = format!(
+                    "book has no module \"{}\", add it as {}",
+                    name,
+                    module_path(&name)
+                );
+                if let Some(suggestion) =
+                    suggestions(&name, state.libs.keys().map(|name| name.as_ref())).first()
+                {
+                    message.push_str(&format!(", did you mean \"{}\"?", suggestion));
+                }
+                return Err(mlua::Error::runtime(message));
+            }
+        };
+
+        requiring.borrow_mut().push(name.clone());
+        let result = Vm::environment(lua).and_then(|env| {
+            // modules require each other through this same function
+            env.raw_set(
+                "require",
+                lua.named_registry_value::<mlua::Function>(REQUIRE)?,
+            )?;
+            lua.load(&*source)
+                .set_name(format!("@{}", module_path(&name)))
+                .set_environment(env)
+                .call::<Value>(())
+        });
+        requiring.borrow_mut().pop();
+
+        // like Lua's own require, a module that returns nothing still counts as loaded
+        let value = match result? {
+            Value::Nil => Value::Boolean(true),
+            Value::Table(table) => {
+                table.set_readonly(true);
+                Value::Table(table)
+            }
+            value => value,
+        };
+        loaded.raw_set(&*name, value.clone())?;
+        Ok(value)
+    })
+}
+
+/// Copies a value one sheet hands another into `to`, the Lua of the receiving sheet.
+/// Tables are copied so sheets can't change each other's, which also lets values cross
+/// between the VMs of layered books. Functions and userdata can't be passed.
+fn transfer(value: Value, to: &Lua, depth: usize) -> mlua::Result<Value> {
+    let value = match value {
+        Value::Nil => Value::Nil,
+        Value::Boolean(boolean) => Value::Boolean(boolean),
+        Value::Integer(integer) => Value::Integer(integer),
+        Value::Number(number) => Value::Number(number),
+        Value::String(string) => Value::String(to.create_string(&*string.as_bytes())?),
+        Value::Table(table) => {
+            if depth == 0 {
+                return Err(mlua::Error::runtime(
+                    "table is nested too deep to be passed between sheets",
+                ));
+            }
+            let copy = to.create_table()?;
+            for pair in table.pairs::<Value, Value>() {
+                let (key, value) = pair?;
+                copy.raw_set(
+                    transfer(key, to, depth - 1)?,
+                    transfer(value, to, depth - 1)?,
+                )?;
+            }
+            Value::Table(copy)
+        }
+        value => {
+            return Err(mlua::Error::runtime(format!(
+                "a {} can't be passed between sheets",
+                value.type_name()
+            )))
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host_of_sheets(sheets: &[(ContentType, &str, &str)]) -> (Host, Vec<Src>) {
+        let host = Host::new();
+        let srcs: Vec<Src> = sheets
+            .iter()
+            .map(|(content_type, name, source)| {
+                host.load(*content_type, name, name, source.to_string())
+                    .unwrap()
+            })
+            .collect();
+        host.set_sheets(
+            sheets
+                .iter()
+                .zip(srcs.iter())
+                .map(|((content_type, name, _), src)| {
+                    (*content_type, Box::from(*name), src.clone())
+                }),
+        );
+        (host, srcs)
+    }
+
+    #[test]
+    fn sheets_call_each_other_through_book() {
+        let (_host, srcs) = host_of_sheets(&[
+            (
+                ContentType::Class,
+                "Pyro",
+                "function SpellLevel(level)\n    return book.call(\"balance\", \"half caster\", \"SpellLevel\", level)\nend\nfunction Known()\n    return book.exists(\"skill\", \"Nope\")\nend\n",
+            ),
+            (
+                ContentType::Balance,
+                "Half Caster",
+                "function SpellLevel(level)\n    return math.floor(level / 2)\nend\n",
+            ),
+        ]);
+        assert_eq!(srcs[0].call::<usize>("SpellLevel", 6).unwrap(), 3);
+        assert!(!srcs[0].call::<bool>("Known", ()).unwrap());
+    }
+
+    #[test]
+    fn require_finds_book_modules_and_reports_cycles() {
+        let host = Host::new();
+        host.set_libs([
+            (
+                Box::from("ladder"),
+                Rc::from("local ladder = {}\nfunction ladder.double(x)\n    return x * 2\nend\nreturn ladder\n"),
+            ),
+            (Box::from("ping"), Rc::from("return require(\"pong\")\n")),
+            (Box::from("pong"), Rc::from("return require(\"lib/ping.lua\")\n")),
+        ]);
+        let load = |source: &str| host.load(ContentType::Class, "Pyro", "Pyro", source.to_string());
+
+        let src = load("local ladder = require(\"ladder\")\nHealth = ladder.double(3)\n").unwrap();
+        assert_eq!(src.get::<usize>("Health").unwrap(), 6);
+
+        let err = load("require(\"ping\")").unwrap_err().to_string();
+        assert!(err.contains("\"ping\" -> \"pong\" -> \"ping\""), "{}", err);
+        let err = load("require(\"ladders\")").unwrap_err().to_string();
+        assert!(err.contains("did you mean \"ladder\"?"), "{}", err);
+    }
+
+    #[test]
+    fn book_reports_cycles_and_unknown_sheets() {
+        let (_host, srcs) = host_of_sheets(&[
+            (
+                ContentType::Class,
+                "Ping",
+                "function Health(level)\n    return book.call(\"class\", \"Pong\", \"Health\", level)\nend\nfunction ArmorRating()\n    return book.call(\"balance\", \"Half Castor\", \"ArmorRating\")\nend\n",
+            ),
+            (
+                ContentType::Class,
+                "Pong",
+                "function Health(level)\n    return book.call(\"class\", \"Ping\", \"Health\", level)\nend\n",
+            ),
+            (ContentType::Balance, "Half Caster", "ArmorRating = 1\n"),
+        ]);
+
+        let err = srcs[0].call::<usize>("Health", 1).unwrap_err().to_string();
+        assert!(
+            err.contains("class \"Ping\" -> class \"Pong\" -> class \"Ping\""),
+            "{}",
+            err
+        );
+        let err = srcs[0]
+            .call::<usize>("ArmorRating", ())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("did you mean \"Half Caster\"?"), "{}", err);
+    }
+}
@@ -0,0 +1,288 @@
+use super::{
+    book::{Book, Page, PageError},
+    character::{Character, Choice, Class},
+    derive::{Derived, Source},
+    lint::normalize,
+    rules::Violation,
+};
+
+/// A level a character could take: the next level of a class it has, or the first level
+/// of a new class with one of its balances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelUp {
+    /// The class as it is once the level is taken.
+    pub class: Class,
+    /// Whether the character doesn't have the class yet.
+    pub new: bool,
+}
+
+/// One choice a level up offers and what can be picked for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offer {
+    pub source: Source,
+    pub name: Box<str>,
+    pub options: Vec<Box<str>>,
+}
+
+/// What taking a level changes about a character.
+#[derive(Debug, Clone)]
+pub struct Changes {
+    pub before: Derived,
+    pub after: Derived,
+    /// Skills the level grants, one granted again counts as gained again.
+    pub skills: Vec<Box<str>>,
+    /// Cybernetics the character didn't have before.
+    pub cybernetics: Vec<Box<str>>,
+    /// Choices the class and balance sheets offer at the new level.
+    pub offers: Vec<Offer>,
+    /// Rules the character breaks with the level but didn't without it.
+    pub violations: Vec<Violation>,
+    /// `Choices` a sheet failed to give, so there may be more to pick than `offers`.
+    pub errors: Vec<(Source, PageError)>,
+}
+
+impl LevelUp {
+    /// Every level `character` could take with the classes and balances of `book`: the
+    /// next level of each class it has, then each class it doesn't, by name, with each
+    /// balance the class allows.
+    pub fn options(character: &Character, book: &Book) -> Vec<Self> {
+        let mut options: Vec<Self> = character
+            .classes
+            .iter()
+            .map(|class| Self {
+                class: Class {
+                    level: class.level + 1,
+                    ..class.clone()
+                },
+                new: false,
+            })
+            .collect();
+
+        let taken: Vec<String> = character
+            .classes
+            .iter()
+            .map(|class| normalize(&class.name))
+            .collect();
+        let mut classes: Vec<_> = book
+            .class
+            .iter()
+            .filter(|class| !taken.contains(&normalize(class.name())))
+            .collect();
+        classes.sort_by(|a, b| a.name().cmp(b.name()));
+        for class in classes {
+            let mut balances = class.balances();
+            if balances.is_empty() {
+                balances = book
+                    .balance
+                    .iter()
+                    .map(|balance| balance.name().into())
+                    .collect();
+                balances.sort();
+            }
+            for balance in balances {
+                options.push(Self {
+                    class: Class {
+                        name: class.name().into(),
+                        balance,
+                        level: 1,
+                    },
+                    new: true,
+                });
+            }
+        }
+        options
+    }
+
+    /// `character` once it has taken this level and made `picks`.
+    pub fn apply(&self, character: &Character, picks: Vec<Choice>) -> Character {
+        let mut character = character.clone();
+        let name = normalize(&self.class.name);
+        match character
+            .classes
+            .iter_mut()
+            .find(|class| normalize(&class.name) == name)
+        {
+            Some(class) if !self.new => class.level = self.class.level,
+            _ => character.classes.push(self.class.clone()),
+        }
+        character.choices.extend(picks);
+        character
+    }
+
+    /// Records `picked` for `offer`.
+    pub fn pick(&self, offer: &Offer, picked: &str) -> Choice {
+        Choice {
+            class: self.class.name.clone(),
+            level: self.class.level,
+            name: offer.name.clone(),
+            picked: picked.into(),
+        }
+    }
+
+    /// Compares `character` before and after taking this level, with the choices the
+    /// new level offers.
+    pub fn changes(&self, character: &Character, book: &Book) -> Changes {
+        let levelled = self.apply(character, vec![]);
+        let before = character.derive(book);
+        let after = levelled.derive(book);
+
+        let mut had = before.skills.names.clone();
+        let skills = after
+            .skills
+            .names
+            .iter()
+            .filter(|name| match had.iter().position(|had| had == *name) {
+                Some(index) => {
+                    had.remove(index);
+                    false
+                }
+                None => true,
+            })
+            .cloned()
+            .collect();
+        let cybernetics = after
+            .cybernetics
+            .names
+            .iter()
+            .filter(|name| !before.cybernetics.names.contains(name))
+            .cloned()
+            .collect();
+
+        let level = self.class.level;
+        let mut choices = vec![];
+        if let Some(class) = book.class.find(&self.class.name) {
+            let source = Source::Class {
+                name: self.class.name.clone(),
+                level,
+            };
+            choices.push((source, class.try_choices(level)));
+        }
+        if let Some(balance) = book.balance.find(&self.class.balance) {
+            let source = Source::Balance {
+                name: self.class.balance.clone(),
+                class: self.class.name.clone(),
+                level,
+            };
+            choices.push((source, balance.try_choices(level)));
+        }
+        let mut offers = vec![];
+        let mut errors = vec![];
+        for (source, choices) in choices {
+            match choices {
+                Ok(choices) => {
+                    for (name, options) in choices.unwrap_or_default() {
+                        offers.push(Offer {
+                            source: source.clone(),
+                            name,
+                            options,
+                        });
+                    }
+                }
+                Err(err) => errors.push((source, err)),
+            }
+        }
+
+        let broken = character.violations(book);
+        let violations = levelled
+            .violations(book)
+            .into_iter()
+            .filter(|violation| !broken.contains(violation))
+            .collect();
+
+        Changes {
+            before,
+            after,
+            skills,
+            cybernetics,
+            offers,
+            violations,
+            errors,
+        }
+    }
+}
+
+impl std::fmt::Display for LevelUp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            self.class.name, self.class.level, self.class.balance
+        )?;
+        if self.new {
+            f.write_str(", new class")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::book::Src;
+    use super::*;
+
+    fn book() -> Book {
+        let mut book = Book::new();
+        book.class.write(
+            "Pyro".into(),
+            Src::load(
+                "Pyro",
+                "Balances = { \"Caster\" }\nfunction Health(level)\n    return level * 4\nend\nfunction Skills(level)\n    if level >= 2 then\n        return { \"Spark\", \"Skill Mastery\" }\n    end\n    return { \"Spark\" }\nend\nfunction Choices(level)\n    if level == 2 then\n        return { [\"Skill Mastery\"] = { \"Stealth\", \"Hacking\" } }\n    end\n    return nil\nend\n",
+            )
+            .unwrap(),
+        );
+        book.class.write(
+            "Brute".into(),
+            Src::load(
+                "Brute",
+                "function Skills(level)\n    return { \"Spark\" }\nend\n",
+            )
+            .unwrap(),
+        );
+        book.balance
+            .write("Caster".into(), Src::load("Caster", "").unwrap());
+        book.balance
+            .write("Martial".into(), Src::load("Martial", "").unwrap());
+        book
+    }
+
+    #[test]
+    fn level_up_shows_what_changes_and_records_picks() {
+        let book = book();
+        let character = Character::test_with_classes(&[("Pyro", "Caster", 1)]);
+        let options: Vec<String> = LevelUp::options(&character, &book)
+            .iter()
+            .map(LevelUp::to_string)
+            .collect();
+        assert_eq!(
+            options,
+            vec![
+                "Pyro 2 (Caster)",
+                "Brute 1 (Caster), new class",
+                "Brute 1 (Martial), new class",
+            ]
+        );
+
+        let level_up = LevelUp::options(&character, &book).remove(0);
+        let changes = level_up.changes(&character, &book);
+        assert_eq!(changes.before.health.total, 4);
+        assert_eq!(changes.after.health.total, 8);
+        assert_eq!(changes.skills, vec![Box::from("Skill Mastery")]);
+        assert_eq!(changes.offers.len(), 1);
+        assert_eq!(&*changes.offers[0].name, "Skill Mastery");
+        assert_eq!(changes.offers[0].options.len(), 2);
+        assert!(changes.violations.is_empty());
+
+        let pick = level_up.pick(&changes.offers[0], "Hacking");
+        let levelled = level_up.apply(&character, vec![pick]);
+        assert_eq!(levelled.level(), 2);
+        assert_eq!(levelled.classes.len(), 1);
+        assert_eq!(&*levelled.choices[0].picked, "Hacking");
+        assert_eq!(levelled.choices[0].level, 2);
+
+        // a second Spark from the new class counts as gained
+        let brute = LevelUp::options(&character, &book).remove(1);
+        let changes = brute.changes(&character, &book);
+        assert_eq!(changes.skills, vec![Box::from("Spark")]);
+        assert_eq!(brute.apply(&character, vec![]).classes.len(), 2);
+    }
+}
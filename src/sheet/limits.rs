@@ -0,0 +1,171 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Bounds on what a sheet's Lua may use, so a broken or hostile homebrew book can't
+/// hang or exhaust the app. They hold while a sheet loads and for every call into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// How long loading a sheet, or a single call into it, may run.
+    pub wall_time: Duration,
+    /// How many more bytes the Lua may hold while a sheet loads or a call into it runs.
+    pub memory: usize,
+    /// How many bytes the Lua of a whole book may hold, as every sheet of it shares one.
+    pub book_memory: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            wall_time: Duration::from_secs(1),
+            memory: 32 * 1024 * 1024,
+            book_memory: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Applies the limits to a fresh Lua. The book's memory holds from now on, the rest
+    /// only while the returned `Watchdog` runs something.
+    pub fn install(&self, lua: &mlua::Lua) -> mlua::Result<Watchdog> {
+        lua.set_memory_limit(self.book_memory)?;
+        let watchdog = Watchdog {
+            lua: lua.clone(),
+            limits: *self,
+            deadline: Rc::new(Cell::new(None)),
+            at_book_memory: Rc::new(Cell::new(false)),
+        };
+        let deadline = Rc::clone(&watchdog.deadline);
+        let wall_time = self.wall_time;
+        lua.set_interrupt(move |_| match deadline.get() {
+            Some(deadline) if Instant::now() > deadline => Err(mlua::Error::runtime(format!(
+                "sheet ran for longer than {} ms",
+                wall_time.as_millis()
+            ))),
+            _ => Ok(mlua::VmState::Continue),
+        });
+        Ok(watchdog)
+    }
+}
+
+/// Ends Lua running past its wall time or memory.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    lua: mlua::Lua,
+    limits: Limits,
+    deadline: Rc<Cell<Option<Instant>>>,
+    /// Whether the running call has less headroom than `memory`, as the book is nearly
+    /// out of memory.
+    at_book_memory: Rc<Cell<bool>>,
+}
+
+impl Watchdog {
+    /// Runs `f` under the limits. Calls made from inside another call share the outer
+    /// call's limits, as several sheets share one Lua.
+    pub fn run<T>(&self, f: impl FnOnce() -> mlua::Result<T>) -> mlua::Result<T> {
+        let outermost = self.deadline.get().is_none();
+        if outermost {
+            let headroom = self.lua.used_memory() + self.limits.memory;
+            self.at_book_memory.set(headroom > self.limits.book_memory);
+            self.lua
+                .set_memory_limit(headroom.min(self.limits.book_memory))?;
+            self.deadline
+                .set(Some(Instant::now() + self.limits.wall_time));
+        }
+        let result = f();
+        if outermost {
+            self.deadline.set(None);
+            let _ = self.lua.set_memory_limit(self.limits.book_memory);
+        }
+        result.map_err(|err| match err {
+            mlua::Error::MemoryError(_) if self.at_book_memory.get() => {
+                mlua::Error::runtime(format!(
+                    "the sheets of the book used more than {} bytes of memory together",
+                    self.limits.book_memory
+                ))
+            }
+            mlua::Error::MemoryError(_) => mlua::Error::runtime(format!(
+                "sheet used more than {} bytes of memory",
+                self.limits.memory
+            )),
+            err => err,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::vm::Vm;
+    use super::*;
+
+    #[test]
+    fn limits_stop_runaway_sheets() {
+        let limits = Limits {
+            wall_time: Duration::from_millis(50),
+            memory: 4 * 1024 * 1024,
+            book_memory: 16 * 1024 * 1024,
+        };
+        for (source, expected) in [
+            ("while true do end", "longer than 50 ms"),
+            (
+                "local t = {}\nfor i = 1, 1e9 do\n    t[i] = i\nend",
+                "more than 4194304 bytes",
+            ),
+        ] {
+            let err = Vm::new(limits)
+                .unwrap()
+                .load("test.class.lua", source, |_, _| Ok(()))
+                .unwrap_err()
+                .to_string();
+            assert!(
+                err.contains(expected),
+                "Failed for source: {}\n{}",
+                source,
+                err
+            );
+        }
+
+        let src = Vm::new(limits)
+            .unwrap()
+            .load(
+                "test.class.lua",
+                "function Health(level)\n    while true do end\nend",
+                |_, _| Ok(()),
+            )
+            .unwrap();
+        let err = src.call::<usize>("Health", 1).unwrap_err().to_string();
+        assert!(err.contains("longer than 50 ms"), "{}", err);
+    }
+
+    #[test]
+    fn sheets_of_a_book_share_its_memory() {
+        let limits = Limits {
+            wall_time: Duration::from_secs(1),
+            memory: 4 * 1024 * 1024,
+            book_memory: 8 * 1024 * 1024,
+        };
+        let vm = Vm::new(limits).unwrap();
+        let mut loaded = vec![];
+        let err = loop {
+            match vm.load(
+                "test.class.lua",
+                // equal strings are shared, so every sheet makes a different one
+                format!("Big = string.rep(\"{}\", 2 * 1024 * 1024)", loaded.len()),
+                |_, _| Ok(()),
+            ) {
+                Ok(src) => loaded.push(src),
+                Err(err) => break err.to_string(),
+            }
+            assert!(loaded.len() < 8, "loaded {} sheets of 2 MiB", loaded.len());
+        };
+        assert!(loaded.len() >= 2, "{}", err);
+        assert!(
+            err.contains("more than 8388608 bytes of memory together"),
+            "{}",
+            err
+        );
+        assert!(vm.lua().used_memory() <= limits.book_memory);
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{
+    book::{Book, ContentType, Page},
+    book_file::sheet_path,
+    character::ClassLevel,
+    diagnostic::BookDiagnostic,
+    locale::Text,
+    schema::LEVELS,
+};
+
+/// What kind of sheet a reference points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Cybernetic,
+    Skill,
+    Balance,
+    Astralic,
+}
+
+impl Target {
+    fn label(&self) -> &'static str {
+        match self {
+            Target::Cybernetic => "cybernetic",
+            Target::Skill => "skill",
+            Target::Balance => "balance",
+            Target::Astralic => "Astralic type",
+        }
+    }
+}
+
+/// A name one sheet uses to point at another.
+#[derive(Debug, Clone)]
+struct Reference {
+    target: Target,
+    name: Box<str>,
+    content_type: ContentType,
+    sheet: Box<str>,
+    path: Box<str>,
+    level: Option<ClassLevel>,
+}
+
+impl Reference {
+    fn diagnostic(&self, message: String) -> BookDiagnostic {
+        let diagnostic = BookDiagnostic::warning(self.path.clone(), message)
+            .with_sheet(self.content_type, self.sheet.clone());
+        match self.level {
+            Some(level) => diagnostic.with_level(level),
+            None => diagnostic,
+        }
+    }
+}
+
+/// Where the sheet behind `page` lives, prefixed with its book once books are layered.
+fn page_path(page: &impl Page, content_type: ContentType) -> Box<str> {
+    origin_path(page.origin(), content_type, page.name())
+}
+
+fn origin_path(origin: Option<&str>, content_type: ContentType, name: &str) -> Box<str> {
+    let path = sheet_path(content_type, name);
+    match origin {
+        Some(origin) => format!("{}/{}", origin, path).into(),
+        None => path.into(),
+    }
+}
+
+/// Every name a class or balance points at, each once, at the first level it shows up.
+fn references(
+    page: &impl Page,
+    content_type: ContentType,
+    balances: Vec<Box<str>>,
+) -> Vec<Reference> {
+    let path = page_path(page, content_type);
+
+    let mut references: Vec<Reference> = vec![];
+    let mut add = |target: Target, name: Box<str>, level: Option<ClassLevel>| {
+        if references
+            .iter()
+            .any(|reference| reference.target == target && reference.name == name)
+        {
+            return;
+        }
+        references.push(Reference {
+            target,
+            name,
+            content_type,
+            sheet: page.name().into(),
+            path: path.clone(),
+            level,
+        });
+    };
+
+    for name in balances {
+        add(Target::Balance, name, None);
+    }
+    for name in page.astralic_types() {
+        add(Target::Astralic, name, None);
+    }
+    for level in LEVELS {
+        for name in page.cybernetics(level) {
+            add(Target::Cybernetic, name, Some(level));
+        }
+        for name in page.skills(level) {
+            add(Target::Skill, name, Some(level));
+        }
+    }
+    references
+}
+
+/// Resolves every name the sheets of `book` use against its sections.
+/// Reports names that point nowhere, names spelled differently in different places
+/// and descriptions missing a translation.
+pub fn lint(book: &Book) -> Vec<BookDiagnostic> {
+    let mut all = vec![];
+    for class in book.class.iter() {
+        all.extend(references(&class, ContentType::Class, class.balances()));
+    }
+    for balance in book.balance.iter() {
+        all.extend(references(&balance, ContentType::Balance, vec![]));
+    }
+
+    for skill in book.skills.iter() {
+        for name in skill.prerequisites() {
+            all.push(Reference {
+                target: Target::Skill,
+                name,
+                content_type: ContentType::Skill,
+                sheet: skill.name().into(),
+                path: page_path(&skill, ContentType::Skill),
+                level: None,
+            });
+        }
+    }
+
+    for spell in book.spells.iter() {
+        let name = spell.astralic_type();
+        if name.is_empty() {
+            continue;
+        }
+        all.push(Reference {
+            target: Target::Astralic,
+            name,
+            content_type: ContentType::Spell,
+            sheet: spell.name().into(),
+            path: page_path(&spell, ContentType::Spell),
+            level: None,
+        });
+    }
+
+    for astralic in book.astralics.iter() {
+        for name in astralic.opposed().into_iter().chain(astralic.synergistic()) {
+            all.push(Reference {
+                target: Target::Astralic,
+                name,
+                content_type: ContentType::Astralic,
+                sheet: astralic.name().into(),
+                path: page_path(&astralic, ContentType::Astralic),
+                level: None,
+            });
+        }
+    }
+
+    let cybernetics: Vec<&str> = book.cybernetics.sheets().map(|(name, _)| name).collect();
+    let balances: Vec<&str> = book.balance.sheets().map(|(name, _)| name).collect();
+    let skills: Vec<&str> = book.skills.sheets().map(|(name, _)| name).collect();
+    let astralics: Vec<&str> = book.astralics.sheets().map(|(name, _)| name).collect();
+
+    let mut diagnostics = vec![];
+    for reference in all.iter() {
+        let known = match reference.target {
+            Target::Cybernetic => &cybernetics,
+            Target::Balance => &balances,
+            Target::Skill => &skills,
+            Target::Astralic => &astralics,
+        };
+        if known.contains(&reference.name.as_ref()) {
+            continue;
+        }
+        diagnostics.push(
+            reference
+                .diagnostic(format!(
+                    "no {} named \"{}\"",
+                    reference.target.label(),
+                    reference.name
+                ))
+                .with_suggestions(suggestions(&reference.name, known.iter().copied())),
+        );
+    }
+    diagnostics.extend(variants(&all, Target::Skill));
+    diagnostics.extend(variants(&all, Target::Astralic));
+    diagnostics.extend(apostrophes(&all));
+    diagnostics.extend(untranslated(book));
+    diagnostics
+}
+
+/// Reports, for every locale some description is written in, the sheets whose
+/// description isn't, so translators can see what is left.
+fn untranslated(book: &Book) -> Vec<BookDiagnostic> {
+    let mut texts: Vec<(Box<str>, ContentType, &str, Text)> = book
+        .sheets()
+        .into_iter()
+        .filter_map(|(content_type, name, sheet)| {
+            let text = sheet.src().try_get::<Text>("Description").ok().flatten()?;
+            let path = origin_path(sheet.origin(), content_type, name);
+            Some((path, content_type, name, text))
+        })
+        .collect();
+    texts.sort_by(|a, b| a.0.cmp(&b.0));
+    let locales: BTreeSet<&str> = texts
+        .iter()
+        .flat_map(|(_, _, _, text)| text.locales())
+        .collect();
+
+    let mut diagnostics = vec![];
+    for locale in locales {
+        for (path, content_type, name, text) in texts.iter() {
+            if !text.has(locale) {
+                diagnostics.push(
+                    BookDiagnostic::info(
+                        path.clone(),
+                        format!("Description has no \"{}\" translation", locale),
+                    )
+                    .with_sheet(*content_type, *name),
+                );
+            }
+        }
+    }
+    diagnostics
+}
+
+const STRAIGHT: char = '\'';
+const CURLY: char = '\u{2019}';
+
+/// Reports names using the less common of straight and curly apostrophes, as
+/// "Spirit's" and "Spirit’s" read the same but never match each other.
+fn apostrophes(references: &[Reference]) -> Vec<BookDiagnostic> {
+    let straight = references
+        .iter()
+        .filter(|reference| reference.name.contains(STRAIGHT))
+        .count();
+    let curly = references
+        .iter()
+        .filter(|reference| reference.name.contains(CURLY))
+        .count();
+    let (odd, common) = match (straight, curly) {
+        (0, _) | (_, 0) => return vec![],
+        (straight, curly) if straight > curly => (CURLY, STRAIGHT),
+        _ => (STRAIGHT, CURLY),
+    };
+
+    references
+        .iter()
+        .filter(|reference| reference.name.contains(odd))
+        .map(|reference| {
+            reference
+                .diagnostic(format!(
+                    "{} \"{}\" uses {} apostrophes while most names use {}",
+                    reference.target.label(),
+                    reference.name,
+                    odd,
+                    common
+                ))
+                .with_suggestions(vec![reference
+                    .name
+                    .replace(odd, &common.to_string())
+                    .into()])
+        })
+        .collect()
+}
+
+/// Reports names of `target` that differ from the most used spelling only by case,
+/// spacing or the kind of apostrophe.
+fn variants(references: &[Reference], target: Target) -> Vec<BookDiagnostic> {
+    let references = references
+        .iter()
+        .filter(|reference| reference.target == target);
+
+    let mut spellings: BTreeMap<String, BTreeMap<&str, usize>> = BTreeMap::new();
+    for reference in references.clone() {
+        *spellings
+            .entry(normalize(&reference.name))
+            .or_default()
+            .entry(&reference.name)
+            .or_default() += 1;
+    }
+
+    let mut diagnostics = vec![];
+    for reference in references {
+        let common = spellings[&normalize(&reference.name)]
+            .iter()
+            .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then(b_name.cmp(a_name)))
+            .map(|(name, _)| *name);
+        match common {
+            Some(common) if common != reference.name.as_ref() => diagnostics.push(
+                reference
+                    .diagnostic(format!(
+                        "{} \"{}\" is spelled differently elsewhere",
+                        target.label(),
+                        reference.name
+                    ))
+                    .with_suggestions(vec![common.into()]),
+            ),
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+/// Folds the differences authors don't mean: case, runs of spaces and typographic apostrophes.
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{02BC}' | '`' | '\u{00B4}' => '\'',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Edits needed to turn `a` into `b`, counted in chars.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Names from `known` that `name` is probably a misspelling of, closest first.
+pub fn suggestions<'k>(name: &str, known: impl IntoIterator<Item = &'k str>) -> Vec<Box<str>> {
+    let wanted = normalize(name);
+    let allowed = (wanted.chars().count() / 4).clamp(1, 3);
+    let mut close: Vec<(usize, &str)> = known
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = distance(&wanted, &normalize(candidate));
+            (distance <= allowed).then_some((distance, candidate))
+        })
+        .collect();
+    close.sort();
+    close.into_iter().map(|(_, name)| name.into()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_folds_case_spaces_and_apostrophes() {
+        for (name, expected) in [
+            ("Spirit\u{2019}s Retaliation", "spirit's retaliation"),
+            ("Spirit's  Retaliation ", "spirit's retaliation"),
+            ("SPIRIT`S RETALIATION", "spirit's retaliation"),
+            ("Wine-Walker", "wine-walker"),
+        ] {
+            assert_eq!(normalize(name), expected, "Failed for name: {}", name);
+        }
+    }
+
+    #[test]
+    fn suggestions_for_near_misses() {
+        let known = [
+            "Spellblade Sheath",
+            "Vessel of the Spirits",
+            "Spirit\u{2019}s Retaliation",
+        ];
+        for (name, expected) in [
+            ("spellblade sheath", vec!["Spellblade Sheath"]),
+            ("Spellblade Sheeth", vec!["Spellblade Sheath"]),
+            ("Spirit's Retaliation", vec!["Spirit\u{2019}s Retaliation"]),
+            ("Vessel of the Spirit", vec!["Vessel of the Spirits"]),
+            ("Cyber Arm", vec![]),
+        ] {
+            let expected: Vec<Box<str>> = expected.into_iter().map(Into::into).collect();
+            assert_eq!(
+                suggestions(name, known),
+                expected,
+                "Failed for name: {}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use mlua::{FromLua, Lua, Value};
+
+/// Locale every book is expected to be written in, used when a text has no variant for
+/// the user's locale.
+pub const FALLBACK: &str = "en";
+
+/// Environment variables naming the locale for messages, in the order POSIX gives them.
+const VARIABLES: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// The user's locale, read from the environment once.
+pub fn current() -> &'static str {
+    static CURRENT: OnceLock<Box<str>> = OnceLock::new();
+    CURRENT.get_or_init(|| from_env(|key| std::env::var(key).ok()))
+}
+
+/// The locale named by the first of `VARIABLES` that is set, English when that is `C`,
+/// `POSIX` or none is set.
+fn from_env(var: impl Fn(&str) -> Option<String>) -> Box<str> {
+    VARIABLES
+        .iter()
+        .filter_map(|key| var(key))
+        .find(|value| !value.is_empty())
+        .and_then(|value| normalize(&value))
+        .unwrap_or_else(|| FALLBACK.into())
+}
+
+/// Turns `de_DE.UTF-8`, `pt-BR` or `EN` into `de_de`, `pt_br` and `en`.
+/// `None` for the `C` and `POSIX` locales and anything that isn't a language tag.
+pub fn normalize(tag: &str) -> Option<Box<str>> {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default();
+    let tag = tag.trim().replace('-', "_").to_lowercase();
+    let language = tag.split('_').next().unwrap_or_default();
+    let valid = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| tag.into())
+}
+
+/// The language part of a normalized locale, `pt` for `pt_br`.
+fn language(locale: &str) -> &str {
+    locale.split('_').next().unwrap_or(locale)
+}
+
+/// Text a sheet gives in one or more locales. Sheets write it as a plain string, which
+/// is English, or as a table keyed by locale.
+///
+/// ```lua
+/// Description = {
+///     en = "Half of everything.",
+///     de = "Von allem die Hälfte.",
+///     pt_BR = "Metade de tudo.",
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Text {
+    variants: BTreeMap<Box<str>, Box<str>>,
+}
+
+impl Text {
+    /// The variant for `locale`, trying its language and then English before settling
+    /// for any variant there is.
+    pub fn get(&self, locale: &str) -> Option<&str> {
+        [locale, language(locale), FALLBACK]
+            .into_iter()
+            .find_map(|locale| self.variants.get(locale))
+            .or_else(|| self.variants.values().next())
+            .map(|text| &**text)
+    }
+
+    /// The variant for the user's locale, empty when there is no text at all.
+    pub fn localized(&self) -> &str {
+        self.get(current()).unwrap_or_default()
+    }
+
+    /// Whether the text was written for `locale` itself, without falling back.
+    pub fn has(&self, locale: &str) -> bool {
+        self.variants.contains_key(locale)
+    }
+
+    /// The locales the text was written for.
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.variants.keys().map(|locale| &**locale)
+    }
+}
+
+impl From<&str> for Text {
+    fn from(english: &str) -> Self {
+        Self {
+            variants: BTreeMap::from([(FALLBACK.into(), english.into())]),
+        }
+    }
+}
+
+impl FromLua for Text {
+    fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+        if let Value::Table(table) = value {
+            let mut variants = BTreeMap::new();
+            for pair in table.pairs::<String, Box<str>>() {
+                let (tag, text) = pair?;
+                let locale = normalize(&tag)
+                    .ok_or_else(|| mlua::Error::runtime(format!("\"{}\" is not a locale", tag)))?;
+                variants.insert(locale, text);
+            }
+            return Ok(Self { variants });
+        }
+        let english = Box::<str>::from_lua(value, lua)?;
+        Ok(Self::from(english.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_comes_from_the_environment() {
+        for (env, expected) in [
+            (vec![], "en"),
+            (vec![("LANG", "de_DE.UTF-8")], "de_de"),
+            (vec![("LANG", "de_DE.UTF-8"), ("LC_ALL", "pt_BR")], "pt_br"),
+            (vec![("LC_ALL", ""), ("LC_MESSAGES", "pt-PT@euro")], "pt_pt"),
+            (vec![("LC_ALL", "C"), ("LANG", "de_DE")], "en"),
+        ] {
+            let found = from_env(|key| {
+                env.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            });
+            assert_eq!(&*found, expected, "Failed for env: {:?}", env);
+        }
+    }
+
+    #[test]
+    fn text_falls_back_to_language_then_english() {
+        let lua = Lua::new();
+        let text: Text = lua
+            .load("{ en = \"Half\", de = \"Hälfte\", pt_BR = \"Metade\" }")
+            .eval()
+            .unwrap();
+        for (locale, expected) in [
+            ("de_at", "Hälfte"),
+            ("pt_br", "Metade"),
+            ("pt_pt", "Half"),
+            ("fr", "Half"),
+        ] {
+            assert_eq!(text.get(locale), Some(expected), "Failed for {}", locale);
+        }
+        assert!(text.has("pt_br"));
+        assert!(!text.has("pt"));
+
+        let plain: Text = lua.load("\"Half\"").eval().unwrap();
+        assert_eq!(plain, Text::from("Half"));
+        assert!(lua
+            .load("{ [\"not a locale\"] = \"Half\" }")
+            .eval::<Text>()
+            .is_err());
+    }
+}
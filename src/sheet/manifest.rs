@@ -0,0 +1,200 @@
+use std::{collections::HashMap, str::FromStr};
+
+use super::{
+    book::{ContentType, Src},
+    locale::Text,
+    rules::RuleChanges,
+};
+
+/// Where the manifest lives inside a book.
+pub const MANIFEST_PATH: &str = "book.lua";
+
+/// The `book.lua` manifest at the root of a book.
+///
+/// ```lua
+/// Title = "Cybernetic TRPG"
+/// Version = "1.0.0"               -- version of this book
+/// Authors = { "computing-fun" }
+/// License = "Apache-2.0"
+/// Description = "The rules every other book builds on." -- or a table keyed by locale
+/// SystemVersion = "0.1.0"         -- version of the game rules the book targets
+///
+/// -- how characters are built, see `RuleChanges`
+/// Rules = { MaxLevel = 20 }
+///
+/// -- sheets of earlier books to take out when this one is layered on top of them
+/// Remove = { class = { "Shadowjack" } }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub title: Box<str>,
+    pub version: Version,
+    pub authors: Vec<Box<str>>,
+    pub license: Box<str>,
+    pub description: Text,
+    pub system_version: Version,
+    pub removes: Vec<(ContentType, Box<str>)>,
+    pub rules: RuleChanges,
+}
+
+impl Manifest {
+    /// Reads the manifest globals, along with a message for every field that is missing or malformed.
+    pub fn read(src: &Src) -> (Self, Vec<String>) {
+        let mut problems = vec![];
+        let manifest = Self {
+            title: text(src, "Title", true, &mut problems),
+            version: version(src, "Version", &mut problems),
+            authors: match src.get::<Option<Vec<Box<str>>>>("Authors") {
+                Ok(authors) => authors.unwrap_or_default(),
+                Err(_) => {
+                    problems.push("Authors should be a list of names".to_string());
+                    vec![]
+                }
+            },
+            license: text(src, "License", false, &mut problems),
+            description: match src.get::<Option<Text>>("Description") {
+                Ok(description) => description.unwrap_or_default(),
+                Err(_) => {
+                    problems.push(
+                        "Description should be a string or a table of strings keyed by locale"
+                            .to_string(),
+                    );
+                    Text::default()
+                }
+            },
+            system_version: version(src, "SystemVersion", &mut problems),
+            removes: removes(src, &mut problems),
+            rules: RuleChanges::read(src, &mut problems),
+        };
+        (manifest, problems)
+    }
+}
+
+fn text(src: &Src, key: &str, required: bool, problems: &mut Vec<String>) -> Box<str> {
+    match src.get::<Option<Box<str>>>(key) {
+        Ok(Some(value)) => value,
+        Ok(None) => {
+            if required {
+                problems.push(format!("{} is missing", key));
+            }
+            Box::default()
+        }
+        Err(_) => {
+            problems.push(format!("{} should be a string", key));
+            Box::default()
+        }
+    }
+}
+
+fn removes(src: &Src, problems: &mut Vec<String>) -> Vec<(ContentType, Box<str>)> {
+    let table = match src.get::<Option<HashMap<String, Vec<Box<str>>>>>("Remove") {
+        Ok(table) => table.unwrap_or_default(),
+        Err(_) => {
+            problems.push("Remove should map content types to lists of names".to_string());
+            return vec![];
+        }
+    };
+
+    let mut removes = vec![];
+    for (content_type, names) in table {
+        match ContentType::try_from(content_type.as_str()) {
+            Ok(content_type) => removes.extend(names.into_iter().map(|name| (content_type, name))),
+            Err(_) => problems.push(format!("Remove: unknown content type \"{}\"", content_type)),
+        }
+    }
+    removes
+}
+
+fn version(src: &Src, key: &str, problems: &mut Vec<String>) -> Version {
+    let value = text(src, key, true, problems);
+    if value.is_empty() {
+        return Version::default();
+    }
+    match value.parse() {
+        Ok(version) => version,
+        Err(err) => {
+            problems.push(format!("{}: {}", key, err));
+            Version::default()
+        }
+    }
+}
+
+/// A `major.minor.patch` semantic version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl FromStr for Version {
+    type Err = ParseVersionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError(s.into());
+        let mut parts = s.trim().split('.').map(|part| part.parse::<u64>());
+        let version = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Self {
+                major,
+                minor,
+                patch,
+            },
+            _ => return Err(err()),
+        };
+        Ok(version)
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseVersionError(Box<str>);
+
+impl std::error::Error for ParseVersionError {}
+
+impl std::fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" is not a version like \"1.0.0\"", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_from_str() {
+        for (text, expected) in [
+            (
+                "1.0.0",
+                Some(Version {
+                    major: 1,
+                    minor: 0,
+                    patch: 0,
+                }),
+            ),
+            (
+                " 0.12.3 ",
+                Some(Version {
+                    major: 0,
+                    minor: 12,
+                    patch: 3,
+                }),
+            ),
+            ("1.0", None),
+            ("1.0.0.0", None),
+            ("1.x.0", None),
+            ("", None),
+        ] {
+            assert_eq!(
+                text.parse::<Version>().ok(),
+                expected,
+                "Failed for version: {}",
+                text
+            );
+        }
+    }
+}
@@ -1,4 +1,17 @@
 pub mod ability;
 pub mod book;
-pub mod character;
 mod book_file;
+pub mod character;
+mod character_file;
+pub mod derive;
+pub mod diagnostic;
+pub mod host;
+pub mod level_up;
+pub mod limits;
+pub mod lint;
+pub mod locale;
+pub mod manifest;
+pub mod rules;
+pub mod schema;
+pub mod shelf;
+pub mod vm;
@@ -0,0 +1,360 @@
+use super::{
+    ability::AbilityName,
+    book::{Book, Src},
+    character::{Character, CharacterLevel},
+    lint::normalize,
+};
+
+/// Which classes of a multiclass character grant saving throw proficiencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SavingThrowRule {
+    /// Only the class the character started with.
+    #[default]
+    FirstClass,
+    /// Every class the character has.
+    EveryClass,
+}
+
+/// How the spell levels of several classes combine into the character's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpellLevelRule {
+    /// The highest spell level any class reaches.
+    #[default]
+    Highest,
+    /// The spell levels of every class added up.
+    Sum,
+}
+
+/// The rules a book sets for building characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Highest character level, counting the levels of every class.
+    pub max_level: CharacterLevel,
+    pub saving_throws: SavingThrowRule,
+    pub spell_levels: SpellLevelRule,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            max_level: 20,
+            saving_throws: SavingThrowRule::default(),
+            spell_levels: SpellLevelRule::default(),
+        }
+    }
+}
+
+/// The `Rules` table of a manifest. Only the rules it names change, so an expansion can
+/// raise the level cap without restating the rest.
+///
+/// ```lua
+/// Rules = {
+///     MaxLevel = 20,
+///     SavingThrows = "FirstClass",      -- or "EveryClass"
+///     SpellLevels = "Highest",          -- or "Sum"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleChanges {
+    pub max_level: Option<CharacterLevel>,
+    pub saving_throws: Option<SavingThrowRule>,
+    pub spell_levels: Option<SpellLevelRule>,
+}
+
+impl RuleChanges {
+    /// Reads the `Rules` global, adding a message to `problems` for every rule it can't.
+    pub fn read(src: &Src, problems: &mut Vec<String>) -> Self {
+        let mut changes = Self::default();
+        let table = match src.get::<Option<mlua::Table>>("Rules") {
+            Ok(Some(table)) => table,
+            Ok(None) => return changes,
+            Err(_) => {
+                problems.push("Rules should be a table".to_string());
+                return changes;
+            }
+        };
+
+        match table.get::<Option<CharacterLevel>>("MaxLevel") {
+            Ok(max_level) => changes.max_level = max_level,
+            Err(_) => problems.push("Rules.MaxLevel should be a whole number".to_string()),
+        }
+        match table.get::<Option<String>>("SavingThrows") {
+            Ok(None) => {}
+            Ok(Some(rule)) => match rule.as_str() {
+                "FirstClass" => changes.saving_throws = Some(SavingThrowRule::FirstClass),
+                "EveryClass" => changes.saving_throws = Some(SavingThrowRule::EveryClass),
+                _ => problems.push(format!(
+                    "Rules.SavingThrows should be \"FirstClass\" or \"EveryClass\", found \"{}\"",
+                    rule
+                )),
+            },
+            Err(_) => problems
+                .push("Rules.SavingThrows should be \"FirstClass\" or \"EveryClass\"".to_string()),
+        }
+        match table.get::<Option<String>>("SpellLevels") {
+            Ok(None) => {}
+            Ok(Some(rule)) => match rule.as_str() {
+                "Highest" => changes.spell_levels = Some(SpellLevelRule::Highest),
+                "Sum" => changes.spell_levels = Some(SpellLevelRule::Sum),
+                _ => problems.push(format!(
+                    "Rules.SpellLevels should be \"Highest\" or \"Sum\", found \"{}\"",
+                    rule
+                )),
+            },
+            Err(_) => {
+                problems.push("Rules.SpellLevels should be \"Highest\" or \"Sum\"".to_string())
+            }
+        }
+        changes
+    }
+}
+
+impl Rules {
+    pub fn apply(&mut self, changes: &RuleChanges) {
+        if let Some(max_level) = changes.max_level {
+            self.max_level = max_level;
+        }
+        if let Some(saving_throws) = changes.saving_throws {
+            self.saving_throws = saving_throws;
+        }
+        if let Some(spell_levels) = changes.spell_levels {
+            self.spell_levels = spell_levels;
+        }
+    }
+
+    /// Everything about `character` that breaks these rules or doesn't match `book`.
+    pub fn check(&self, character: &Character, book: &Book) -> Vec<Violation> {
+        let mut violations = vec![];
+        if character.classes.is_empty() {
+            violations.push(Violation::new(None, "has no class"));
+        }
+        if character.level() > self.max_level {
+            violations.push(Violation::new(
+                None,
+                format!(
+                    "is level {}, above the highest level of {}",
+                    character.level(),
+                    self.max_level
+                ),
+            ));
+        }
+
+        let mut seen: Vec<String> = vec![];
+        for (index, class) in character.classes.iter().enumerate() {
+            let name = Some(class.name.clone());
+            if class.level == 0 {
+                violations.push(Violation::new(name.clone(), "has level 0"));
+            }
+            // the rest was checked for the class when it was first taken
+            if seen.contains(&normalize(&class.name)) {
+                violations.push(Violation::new(name, "is taken more than once"));
+                continue;
+            }
+            seen.push(normalize(&class.name));
+
+            let known_balance = book.balance.find(&class.balance).is_some();
+            if !known_balance {
+                violations.push(Violation::new(
+                    name.clone(),
+                    format!("has the balance \"{}\", which no book has", class.balance),
+                ));
+            }
+            let sheet = match book.class.find(&class.name) {
+                Some(sheet) => sheet,
+                None => {
+                    violations.push(Violation::new(name, "is not a class of any book"));
+                    continue;
+                }
+            };
+
+            let balances = sheet.balances();
+            let wanted = normalize(&class.balance);
+            // an unknown balance was already reported, whether the class allows it or not
+            if known_balance
+                && !balances.is_empty()
+                && !balances.iter().any(|name| normalize(name) == wanted)
+            {
+                violations.push(Violation::new(
+                    name.clone(),
+                    format!(
+                        "can't be taken as {}, only as {}",
+                        class.balance,
+                        balances.join(", ")
+                    ),
+                ));
+            }
+
+            // only a class the character moves into, not the one it started with
+            if index == 0 {
+                continue;
+            }
+            for (ability, score) in sheet.multiclass_prerequisites() {
+                let ability = match AbilityName::try_from(ability.as_ref()) {
+                    Ok(ability) => ability,
+                    Err(_) => {
+                        violations.push(Violation::new(
+                            name.clone(),
+                            format!("needs {} to multiclass, which is not an ability", ability),
+                        ));
+                        continue;
+                    }
+                };
+                let has = character.abilities.get(ability).score();
+                if has < score {
+                    violations.push(Violation::new(
+                        name.clone(),
+                        format!("needs {} {} to multiclass, has {}", ability, score, has),
+                    ));
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// Something about a character the rules don't allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The class at fault, `None` when it is the character as a whole.
+    pub class: Option<Box<str>>,
+    pub message: Box<str>,
+}
+
+impl Violation {
+    fn new(class: Option<Box<str>>, message: impl Into<Box<str>>) -> Self {
+        Self {
+            class,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.class {
+            Some(class) => write!(f, "{} {}", class, self.message),
+            None => write!(f, "character {}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ability::Ability, character::ClassLevel};
+    use super::*;
+
+    fn book() -> Book {
+        let mut book = Book::new();
+        book.class.write(
+            "Pyro".into(),
+            Src::load(
+                "Pyro",
+                "SavingThrows = { \"Astralic\" }\nBalances = { \"Caster\" }\nMulticlassPrerequisites = { Astralic = 13 }\nfunction SpellLevel(level)\n    return level\nend\n",
+            )
+            .unwrap(),
+        );
+        book.class.write(
+            "Brute".into(),
+            Src::load(
+                "Brute",
+                "SavingThrows = { \"Grit\" }\nfunction SpellLevel(level)\n    return 1\nend\n",
+            )
+            .unwrap(),
+        );
+        book.balance
+            .write("Caster".into(), Src::load("Caster", "").unwrap());
+        book
+    }
+
+    fn character(classes: &[(&str, &str, ClassLevel)]) -> Character {
+        let mut character = Character::test_with_classes(classes);
+        character.abilities.astralic = Ability::from_score(12);
+        character
+    }
+
+    fn messages(character: &Character, book: &Book) -> Vec<String> {
+        book.rules()
+            .check(character, book)
+            .iter()
+            .map(Violation::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn rules_catch_what_a_book_does_not_allow() {
+        let mut book = book();
+        assert!(messages(&character(&[("Pyro", "Caster", 20)]), &book).is_empty());
+
+        assert_eq!(
+            messages(
+                &character(&[("Brute", "Caster", 18), ("Pyro", "Caster", 3)]),
+                &book
+            ),
+            vec![
+                "character is level 21, above the highest level of 20",
+                "Pyro needs Astralic 13 to multiclass, has 12",
+            ]
+        );
+        assert_eq!(
+            messages(
+                &character(&[("Pyro", "Martial", 1), ("pyro", "Caster", 0)]),
+                &book
+            ),
+            vec![
+                "Pyro has the balance \"Martial\", which no book has",
+                "pyro has level 0",
+                "pyro is taken more than once",
+            ]
+        );
+
+        let multiclass = character(&[("Pyro", "Caster", 3), ("Brute", "Caster", 2)]);
+        let proficient = |character: &Character, book: &Book| -> Vec<AbilityName> {
+            character
+                .saving_throws(book)
+                .into_iter()
+                .filter(|throw| throw.proficient)
+                .map(|throw| throw.ability)
+                .collect()
+        };
+        assert_eq!(proficient(&multiclass, &book), vec![AbilityName::Astralic]);
+        assert_eq!(multiclass.derive(&book).spell_level.total, 3);
+
+        let changes = Src::load(
+            "book",
+            "Rules = { MaxLevel = 30, SavingThrows = \"EveryClass\", SpellLevels = \"Sum\" }",
+        )
+        .unwrap();
+        let mut problems = vec![];
+        book.rules
+            .apply(&RuleChanges::read(&changes, &mut problems));
+        assert!(problems.is_empty());
+        assert_eq!(book.rules().max_level, 30);
+        assert_eq!(
+            proficient(&multiclass, &book),
+            vec![AbilityName::Grit, AbilityName::Astralic]
+        );
+        assert_eq!(multiclass.derive(&book).spell_level.total, 4);
+
+        let broken = Src::load(
+            "book",
+            "Rules = { MaxLevel = \"lots\", SavingThrows = {}, SpellLevels = \"Most\" }",
+        )
+        .unwrap();
+        RuleChanges::read(&broken, &mut problems);
+        assert_eq!(
+            problems,
+            vec![
+                "Rules.MaxLevel should be a whole number",
+                "Rules.SavingThrows should be \"FirstClass\" or \"EveryClass\"",
+                "Rules.SpellLevels should be \"Highest\" or \"Sum\", found \"Most\"",
+            ]
+        );
+
+        // a balance the class allows is still wrong for not being in any book
+        book.balance.remove("Caster");
+        assert_eq!(
+            messages(&character(&[("Pyro", "Caster", 1)]), &book),
+            vec!["Pyro has the balance \"Caster\", which no book has"]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use mlua::Value;
+
+use super::{
+    ability::AbilityName,
+    book::{ContentType, Src},
+    character::ClassLevel,
+    diagnostic::BookDiagnostic,
+    locale,
+};
+
+use Field::{Global, PerLevel};
+use Shape::{Choices, Count, LocalizedText, Number, Scores, Text, TextList};
+
+/// Class levels every level dependent function is checked with.
+pub const LEVELS: std::ops::RangeInclusive<ClassLevel> = 1..=20;
+
+/// What a sheet global holds, or what a level dependent function returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Text,
+    /// A string, or a table of strings keyed by locale.
+    LocalizedText,
+    /// A whole number that is zero or more.
+    Count,
+    /// Any number that is zero or more.
+    Number,
+    TextList,
+    /// A table of whole numbers keyed by ability name.
+    Scores,
+    /// A table of string lists keyed by the name of the choice.
+    Choices,
+}
+
+impl Shape {
+    fn check(&self, value: &Value) -> Result<(), String> {
+        match (self, value) {
+            (Shape::Text | Shape::LocalizedText, Value::String(_)) => Ok(()),
+            (Shape::LocalizedText, Value::Table(table)) => {
+                for pair in table.clone().pairs::<Value, Value>() {
+                    let (key, value) = pair.map_err(|err| err.to_string())?;
+                    let locale = match &key {
+                        Value::String(key) => locale::normalize(&key.to_string_lossy()),
+                        _ => None,
+                    };
+                    if locale.is_none() {
+                        return Err(format!(
+                            "expected {}, found a table with the key {}",
+                            self,
+                            key.to_string().unwrap_or_else(|_| key.type_name().into())
+                        ));
+                    }
+                    if !matches!(value, Value::String(_)) {
+                        return Err(format!(
+                            "expected {}, found a table holding a {}",
+                            self,
+                            value.type_name()
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (Shape::Count, Value::Integer(number)) if *number >= 0 => Ok(()),
+            (Shape::Count, Value::Number(number)) if *number >= 0.0 && number.fract() == 0.0 => {
+                Ok(())
+            }
+            (Shape::Number, Value::Integer(number)) if *number >= 0 => Ok(()),
+            (Shape::Number, Value::Number(number)) if *number >= 0.0 => Ok(()),
+            (Shape::TextList, Value::Table(table)) => {
+                for pair in table.clone().pairs::<Value, Value>() {
+                    let (key, value) = pair.map_err(|err| err.to_string())?;
+                    if !matches!(key, Value::Integer(_) | Value::Number(_)) {
+                        return Err(format!(
+                            "expected {}, found a table with a {} key",
+                            self,
+                            key.type_name()
+                        ));
+                    }
+                    if !matches!(value, Value::String(_)) {
+                        return Err(format!(
+                            "expected {}, found a list holding a {}",
+                            self,
+                            value.type_name()
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (Shape::Scores, Value::Table(table)) => {
+                for pair in table.clone().pairs::<Value, Value>() {
+                    let (key, value) = pair.map_err(|err| err.to_string())?;
+                    let ability = match &key {
+                        Value::String(key) => AbilityName::try_from(&*key.to_string_lossy()).ok(),
+                        _ => None,
+                    };
+                    if ability.is_none() {
+                        return Err(format!(
+                            "expected {}, found a table with the key {}",
+                            self,
+                            key.to_string().unwrap_or_else(|_| key.type_name().into())
+                        ));
+                    }
+                    let whole = match value {
+                        Value::Integer(_) => true,
+                        Value::Number(number) => number.fract() == 0.0,
+                        _ => false,
+                    };
+                    if !whole {
+                        return Err(format!(
+                            "expected {}, found a table holding a {}",
+                            self,
+                            value.type_name()
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (Shape::Choices, Value::Table(table)) => {
+                for pair in table.clone().pairs::<Value, Value>() {
+                    let (key, value) = pair.map_err(|err| err.to_string())?;
+                    let name = match &key {
+                        Value::String(key) => key.to_string_lossy(),
+                        _ => {
+                            return Err(format!(
+                                "expected {}, found a table with a {} key",
+                                self,
+                                key.type_name()
+                            ))
+                        }
+                    };
+                    Shape::TextList
+                        .check(&value)
+                        .map_err(|err| format!("{} under {}", err, name))?;
+                }
+                Ok(())
+            }
+            _ => Err(format!("expected {}, found {}", self, value.type_name())),
+        }
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shape::Text => f.write_str("a string"),
+            Shape::LocalizedText => f.write_str("a string or a table of strings keyed by locale"),
+            Shape::Count => f.write_str("a whole number of zero or more"),
+            Shape::Number => f.write_str("a number of zero or more"),
+            Shape::TextList => f.write_str("a list of strings"),
+            Shape::Scores => f.write_str("a table of whole numbers keyed by ability name"),
+            Shape::Choices => f.write_str("a table of lists of strings keyed by name"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Field {
+    /// A global value.
+    Global(&'static str, Shape),
+    /// A global function called with a class level.
+    PerLevel(&'static str, Shape),
+}
+
+const CLASS: &[Field] = &[
+    Global("Description", LocalizedText),
+    Global("AstralicTypes", TextList),
+    Global("SavingThrows", TextList),
+    Global("Balances", TextList),
+    Global("MulticlassPrerequisites", Scores),
+    PerLevel("Skills", TextList),
+    PerLevel("Cybernetics", TextList),
+    PerLevel("Choices", Choices),
+    PerLevel("Health", Count),
+    PerLevel("ArmorRating", Count),
+    PerLevel("SpellLevel", Count),
+];
+
+const BALANCE: &[Field] = &[
+    Global("Description", LocalizedText),
+    Global("AstralicTypes", TextList),
+    Global("SavingThrows", TextList),
+    PerLevel("Skills", TextList),
+    PerLevel("Cybernetics", TextList),
+    PerLevel("Choices", Choices),
+    PerLevel("Health", Count),
+    PerLevel("ArmorRating", Count),
+    PerLevel("SpellLevel", Count),
+];
+
+/// Fields a sheet of the given type may define. Every one of them is optional.
+pub fn fields(content_type: ContentType) -> &'static [Field] {
+    match content_type {
+        ContentType::Race => &[
+            Global("Description", LocalizedText),
+            Global("Speed", Count),
+            Global("Size", Text),
+            Global("Age", Text),
+            Global("Languages", TextList),
+        ],
+        ContentType::Class => CLASS,
+        ContentType::Balance => BALANCE,
+        ContentType::Cybernetic => &[
+            Global("Description", LocalizedText),
+            Global("BodyPart", Text),
+            Global("Cost", Count),
+            Global("Effects", TextList),
+        ],
+        ContentType::Skill => &[
+            Global("Description", LocalizedText),
+            Global("Prerequisites", TextList),
+            Global("ActionCost", Count),
+            Global("UsesPerRest", Count),
+            Global("Tags", TextList),
+        ],
+        ContentType::Spell => &[
+            Global("Description", LocalizedText),
+            Global("Level", Count),
+            Global("AstralicType", Text),
+            Global("ActionPoints", Count),
+            Global("Range", Text),
+            Global("Duration", Text),
+        ],
+        ContentType::Astralic => &[
+            Global("Description", LocalizedText),
+            Global("CastingAbility", Text),
+            Global("Opposed", TextList),
+            Global("Synergistic", TextList),
+        ],
+        ContentType::Weapon => &[
+            Global("Description", LocalizedText),
+            Global("Damage", Text),
+            Global("Properties", TextList),
+            Global("Mastery", Text),
+            Global("Cost", Count),
+            Global("Weight", Number),
+        ],
+        ContentType::Armor => &[
+            Global("Description", LocalizedText),
+            Global("ArmorRating", Count),
+            Global("Requirements", TextList),
+            Global("Cost", Count),
+            Global("Weight", Number),
+        ],
+        ContentType::Gear => &[
+            Global("Description", LocalizedText),
+            Global("Cost", Count),
+            Global("Weight", Number),
+        ],
+    }
+}
+
+/// Checks the globals a sheet defines against its schema and calls every level dependent
+/// function for each of `LEVELS`, reporting anything that has the wrong shape.
+pub fn validate(
+    path: &str,
+    content_type: ContentType,
+    name: &str,
+    src: &Src,
+) -> Vec<BookDiagnostic> {
+    let mut diagnostics = vec![];
+    let diagnostic =
+        |message: String| BookDiagnostic::error(path, message).with_sheet(content_type, name);
+
+    for field in fields(content_type) {
+        match *field {
+            Global(key, shape) => match src.get::<Value>(key) {
+                Ok(Value::Nil) => {}
+                Ok(value) => {
+                    if let Err(message) = shape.check(&value) {
+                        diagnostics.push(diagnostic(format!("{}: {}", key, message)));
+                    }
+                }
+                Err(err) => diagnostics.push(diagnostic(format!("{}: {}", key, err))),
+            },
+            PerLevel(key, shape) => {
+                match src.get::<Value>(key) {
+                    Ok(Value::Nil) => continue,
+                    Ok(Value::Function(_)) => {}
+                    Ok(value) => {
+                        diagnostics.push(diagnostic(format!(
+                            "{}: expected a function taking the class level, found {}",
+                            key,
+                            value.type_name()
+                        )));
+                        continue;
+                    }
+                    Err(err) => {
+                        diagnostics.push(diagnostic(format!("{}: {}", key, err)));
+                        continue;
+                    }
+                }
+
+                for level in LEVELS {
+                    let result = match src.call::<Value>(key, level) {
+                        Ok(value) => shape.check(&value).map_err(|message| {
+                            diagnostic(format!("{} returned the wrong shape: {}", key, message))
+                        }),
+                        Err(err) => {
+                            Err(BookDiagnostic::lua(path, &err).with_sheet(content_type, name))
+                        }
+                    };
+                    if let Err(problem) = result {
+                        diagnostics.push(problem.with_level(level));
+                        // one broken level is enough to point at the function
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> Vec<BookDiagnostic> {
+        let src = Src::load("Pyro", source).unwrap();
+        validate("class/Pyro.class.lua", ContentType::Class, "Pyro", &src)
+    }
+
+    #[test]
+    fn wrong_globals_and_shapes_are_reported() {
+        let diagnostics = check("AstralicTypes = \"x\"\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            &*diagnostics[0].message,
+            "AstralicTypes: expected a list of strings, found string"
+        );
+        assert_eq!(diagnostics[0].level, None);
+
+        let diagnostics = check("function Health(level)\n    return { level }\nend\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            &*diagnostics[0].message,
+            "Health returned the wrong shape: expected a whole number of zero or more, found table"
+        );
+        assert_eq!(diagnostics[0].level, Some(1));
+
+        assert!(check(
+            "AstralicTypes = { \"Fire\" }\nfunction Health(level)\n    return level * 2\nend\n"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn broken_levels_name_the_sheet_and_level() {
+        let diagnostics = check(
+            "function Health(level)\n    if level == 20 then\n        error(\"too high\")\n    end\n    return level\nend\n",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].content_type, Some(ContentType::Class));
+        assert_eq!(diagnostics[0].sheet.as_deref(), Some("Pyro"));
+        assert_eq!(diagnostics[0].level, Some(*LEVELS.end()));
+        assert!(diagnostics[0].to_string().ends_with("(class level 20)"));
+        assert!(diagnostics[0].message.contains("too high"));
+    }
+}
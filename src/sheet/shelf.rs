@@ -0,0 +1,72 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use super::book::Book;
+
+/// An ordered stack of books, the base game first and every expansion layered on top.
+#[derive(Debug)]
+pub struct Shelf {
+    books: Vec<Book>,
+    /// Every book layered into one, built again only when a book is reloaded.
+    merged: Rc<Book>,
+}
+
+impl Shelf {
+    pub fn new(books: Vec<Book>) -> Self {
+        let merged = Rc::new(Self::merge(&books));
+        Self { books, merged }
+    }
+
+    pub fn books(&self) -> &[Book] {
+        &self.books
+    }
+
+    /// Every book title, bottom to top.
+    pub fn title(&self) -> String {
+        self.books
+            .iter()
+            .map(|book| book.title())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+
+    /// Every book layered, bottom to top, into a single book.
+    pub fn merged(&self) -> Rc<Book> {
+        Rc::clone(&self.merged)
+    }
+
+    fn merge(books: &[Book]) -> Book {
+        let mut merged = Book::new();
+        for book in books.iter() {
+            merged.layer(book);
+        }
+        merged
+    }
+
+    /// Folders of the books that were not read from a zip.
+    pub fn directories(&self) -> Vec<PathBuf> {
+        self.books
+            .iter()
+            .filter_map(|book| book.directory().map(Path::to_path_buf))
+            .collect()
+    }
+
+    /// Hands a changed file to the folder book it belongs to.
+    pub fn reload_file(&mut self, path: &Path) {
+        let mut reloaded = false;
+        for book in self.books.iter_mut() {
+            if book
+                .directory()
+                .is_some_and(|directory| path.starts_with(directory))
+            {
+                book.reload_file(path);
+                reloaded = true;
+            }
+        }
+        if reloaded {
+            self.merged = Rc::new(Self::merge(&self.books));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::rc::Rc;
+
+use mlua::{Lua, Table, Value};
+
+use super::{
+    book::PageError,
+    limits::{Limits, Watchdog},
+};
+
+/// Registry key of the read-only table every sheet environment falls back to.
+const BASE: &str = "sheet_base";
+
+/// Globals of Luau that would let a sheet reach the environment of whoever called it or
+/// the VM's real globals.
+const HIDDEN: &[&str] = &["getfenv", "setfenv", "_G"];
+
+/// A sandboxed Luau VM. Every sheet loaded into it gets its own environment table,
+/// so a book needs one VM rather than one per sheet.
+#[derive(Debug, Clone)]
+pub struct Vm {
+    lua: Lua,
+    watchdog: Watchdog,
+}
+
+impl Vm {
+    pub fn new(limits: Limits) -> mlua::Result<Self> {
+        let lua = Lua::new_with(mlua::StdLib::ALL_SAFE, mlua::LuaOptions::new())?;
+        let watchdog = limits.install(&lua)?;
+
+        let base = lua.create_table()?;
+        for pair in lua.globals().pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            if let Value::String(name) = &key {
+                if HIDDEN.iter().any(|hidden| name == hidden) {
+                    continue;
+                }
+            }
+            base.raw_set(key, value)?;
+        }
+        // makes the standard libraries read-only along with the globals
+        lua.sandbox(true)?;
+        base.set_readonly(true);
+        lua.set_named_registry_value(BASE, base)?;
+
+        Ok(Self { lua, watchdog })
+    }
+
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// A fresh table for a sheet's globals, which reads the standard libraries through.
+    pub fn environment(lua: &Lua) -> mlua::Result<Table> {
+        let meta = lua.create_table()?;
+        meta.raw_set("__index", lua.named_registry_value::<Table>(BASE)?)?;
+        // hides the metatable from getmetatable and keeps setmetatable off it
+        meta.raw_set("__metatable", false)?;
+        meta.set_readonly(true);
+
+        let env = lua.create_table()?;
+        env.raw_set("_G", &env)?;
+        env.set_metatable(Some(meta));
+        Ok(env)
+    }
+
+    /// Runs a sheet's top level code in its own environment, `name` is used as the chunk
+    /// name in Luau errors. `prepare` gets the environment first to set globals on it.
+    pub fn load(
+        &self,
+        name: &str,
+        source: impl Into<Rc<str>>,
+        prepare: impl FnOnce(&Lua, &Table) -> mlua::Result<()>,
+    ) -> mlua::Result<Src> {
+        let source = source.into();
+        let env = Self::environment(&self.lua)?;
+        prepare(&self.lua, &env)?;
+        self.watchdog.run(|| {
+            self.lua
+                .load(&*source)
+                .set_name(format!("@{}", name))
+                .set_environment(env.clone())
+                .exec()
+        })?;
+        Ok(Src {
+            lua: self.lua.clone(),
+            env,
+            source,
+            watchdog: self.watchdog.clone(),
+        })
+    }
+}
+
+/// A loaded sheet: its source and the globals its top level code left behind.
+#[derive(Debug, Clone)]
+pub struct Src {
+    lua: Lua,
+    env: Table,
+    source: Rc<str>,
+    watchdog: Watchdog,
+}
+
+impl Src {
+    /// Runs a sheet's top level code in a VM of its own.
+    pub fn load(name: &str, source: impl Into<Rc<str>>) -> mlua::Result<Self> {
+        Vm::new(Limits::default())?.load(name, source, |_, _| Ok(()))
+    }
+
+    pub fn src(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// The Lua code the sheet was loaded from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn get<T>(&self, key: impl mlua::IntoLua) -> mlua::Result<T>
+    where
+        T: mlua::FromLua,
+    {
+        self.env.raw_get::<T>(key)
+    }
+
+    pub fn set(&self, key: impl mlua::IntoLua, value: impl mlua::IntoLua) -> mlua::Result<()> {
+        self.env.raw_set(key, value)
+    }
+
+    /// Reads a global, `None` when the sheet doesn't define it.
+    pub fn try_get<T>(&self, key: &str) -> Result<Option<T>, PageError>
+    where
+        T: mlua::FromLua,
+    {
+        match self.env.raw_get::<Value>(key) {
+            Ok(Value::Nil) => Ok(None),
+            Ok(value) => T::from_lua(value, &self.lua)
+                .map(Some)
+                .map_err(|err| PageError::lua(key, &err)),
+            Err(err) => Err(PageError::lua(key, &err)),
+        }
+    }
+
+    /// Calls a global function, `None` when the sheet doesn't define it or it returns nil.
+    pub fn try_call<T>(
+        &self,
+        key: &str,
+        args: impl mlua::IntoLuaMulti,
+    ) -> Result<Option<T>, PageError>
+    where
+        T: mlua::FromLua,
+    {
+        let func = match self.try_get::<Value>(key)? {
+            None => return Ok(None),
+            Some(Value::Function(func)) => func,
+            Some(value) => {
+                return Err(PageError::new(
+                    key,
+                    format!("expected a function, found {}", value.type_name()),
+                ))
+            }
+        };
+        match self.watchdog.run(|| func.call::<Value>(args)) {
+            Ok(Value::Nil) => Ok(None),
+            Ok(value) => T::from_lua(value, &self.lua)
+                .map(Some)
+                .map_err(|err| PageError::lua(key, &err)),
+            Err(err) => Err(PageError::lua(key, &err)),
+        }
+    }
+
+    pub fn func(&self, key: impl mlua::IntoLua) -> mlua::Result<mlua::Function> {
+        self.get::<mlua::Function>(key)
+    }
+
+    pub fn call<T>(&self, key: impl mlua::IntoLua, args: impl mlua::IntoLuaMulti) -> mlua::Result<T>
+    where
+        T: mlua::FromLuaMulti,
+    {
+        self.watchdog.run(|| self.func(key)?.call(args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_call_tells_absent_from_broken() {
+        let src = Src::load(
+            "test.balance.lua",
+            "function Health(level)\n    return level * 2\nend\nfunction ArmorRating(level)\n    error(\"no armor table\")\nend\nSpellLevel = 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(src.try_call::<usize>("Health", 2).unwrap(), Some(4));
+        assert_eq!(src.try_call::<usize>("Skills", 2).unwrap(), None);
+        let err = src.try_call::<usize>("ArmorRating", 2).unwrap_err();
+        assert!(err.message.contains("no armor table"), "{}", err);
+        assert!(!err.traceback.is_empty(), "{:?}", err);
+        assert!(src.try_call::<usize>("SpellLevel", 2).is_err());
+    }
+
+    #[test]
+    fn sheets_in_one_vm_keep_to_their_own_globals() {
+        let vm = Vm::new(Limits::default()).unwrap();
+        let first = vm
+            .load(
+                "first",
+                "Speed = 30\nfunction Bump()\n    Speed = Speed + 1\n    return Speed\nend\n",
+                |_, _| Ok(()),
+            )
+            .unwrap();
+        let second = vm
+            .load("second", "Speed = 10\nstring.upper = nil\n", |_, _| Ok(()))
+            .unwrap_err();
+        assert!(second.to_string().contains("readonly"), "{}", second);
+
+        let third = vm
+            .load(
+                "third",
+                "Fenv = getfenv\nLeak = getmetatable(_G)\n",
+                |_, _| Ok(()),
+            )
+            .unwrap();
+        assert_eq!(first.call::<usize>("Bump", ()).unwrap(), 31);
+        assert_eq!(third.get::<Option<usize>>("Speed").unwrap(), None);
+        assert!(third.get::<Value>("Fenv").unwrap().is_nil());
+        assert!(!third.get::<bool>("Leak").unwrap());
+    }
+}
@@ -1,22 +1,58 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use gtk4::{
-    glib::ExitCode, prelude::*, Application, ApplicationWindow, Box, Button, ListBox, ListBoxRow,
-    Orientation, ScrolledWindow, Separator,
+    glib::ExitCode, prelude::*, Application, ApplicationWindow, Box, Button, Expander, Grid,
+    HeaderBar, ListBox, ListBoxRow, Orientation, ScrolledWindow, Separator,
 };
 
-use crate::sheet::book::{Book, Page};
+use crate::dialog;
+use crate::sheet::{
+    book::{Book, ContentType, Item, Page, PageError, SkillSheet},
+    character::{Character, CharacterFile, ClassLevel},
+    derive::{Granted, Stat},
+    diagnostic::{BookDiagnostic, Severity},
+    lint,
+    schema::LEVELS,
+    shelf::Shelf,
+};
+
+use super::{basic_lable, level_up, markdown, name_tag_content, watch, APP_ID};
 
-use super::{basic_lable, name_tag_content, APP_ID};
+/// Scroll targets for every section (`None`) and sheet (`Some(name)`) shown in the content.
+type Targets = Rc<RefCell<HashMap<(ContentType, Option<std::boxed::Box<str>>), Box>>>;
 
-struct Helper<'a> {
-    content_window: &'a ScrolledWindow,
-    content: &'a Box,
-    sidebar: &'a ListBox,
+fn scroll_to(content_window: &ScrolledWindow, target: &Box) {
+    content_window
+        .vadjustment()
+        .set_value(target.allocation().y() as f64);
 }
 
-impl Helper<'_> {
-    fn add_anchor(&self, name: &str) {
+#[derive(Clone)]
+struct Helper {
+    window: ApplicationWindow,
+    content_window: ScrolledWindow,
+    content: Box,
+    sidebar: ListBox,
+    targets: Targets,
+    /// The character opened alongside the books, if any.
+    character: Rc<RefCell<Option<CharacterFile>>>,
+    /// Folders of the books whose changes can't be watched.
+    watch_problems: Rc<RefCell<Vec<BookDiagnostic>>>,
+}
+
+impl Helper {
+    /// Removes everything `render` added so the book can be shown again.
+    fn clear(&self) {
+        while let Some(child) = self.content.first_child() {
+            self.content.remove(&child);
+        }
+        while let Some(child) = self.sidebar.first_child() {
+            self.sidebar.remove(&child);
+        }
+        self.targets.borrow_mut().clear();
+    }
+
+    fn add_anchor(&self, name: &str) -> Box {
         let target = Box::new(Orientation::Horizontal, 0);
         self.content.append(&target);
 
@@ -25,19 +61,652 @@ impl Helper<'_> {
         sidebar_btn.set_has_frame(false);
 
         let content_window_ref = self.content_window.clone();
+        let target_ref = target.clone();
         sidebar_btn.connect_clicked(move |_btn| {
-            content_window_ref
-                .vadjustment()
-                .set_value(target.allocation().y() as f64);
+            scroll_to(&content_window_ref, &target_ref);
         });
 
         let sibebar_row = ListBoxRow::new();
         sibebar_row.set_child(Some(&sidebar_btn));
         self.sidebar.append(&sibebar_row);
+
+        target
+    }
+
+    fn add_section(&self, content_type: ContentType) {
+        let target = self.add_anchor(content_type.label());
+        self.targets
+            .borrow_mut()
+            .insert((content_type, None), target);
+    }
+
+    fn add_target(&self, content_type: ContentType, name: &str) {
+        let target = Box::new(Orientation::Horizontal, 0);
+        self.content.append(&target);
+        self.targets
+            .borrow_mut()
+            .insert((content_type, Some(name.into())), target);
+    }
+
+    fn add_character(&self, shelf: &Shelf, book: &Book) {
+        let character = self.character.borrow();
+        let file = match character.as_ref() {
+            Some(file) => file,
+            None => return,
+        };
+        let character = &file.character;
+        self.add_anchor("Character");
+
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+        for change in file.book_changes(shelf) {
+            let label = basic_lable(&change);
+            label.add_css_class("warning");
+            details.append(&label);
+        }
+        for violation in character.violations(book) {
+            let label = basic_lable(&violation.to_string());
+            label.add_css_class("error");
+            details.append(&label);
+        }
+        details.append(&markdown::render(&character.description));
+        details.append(&fields_maker("Race", vec![character.race.name.clone()]));
+        details.append(&fields_maker(
+            "Classes",
+            character
+                .classes
+                .iter()
+                .map(|class| format!("{} {} ({})", class.name, class.level, class.balance).into())
+                .collect(),
+        ));
+        details.append(&fields_maker(
+            "Level",
+            vec![character.level().to_string().into()],
+        ));
+        details.append(&abilities_maker(character, book));
+
+        let derived = character.derive(book);
+        for source in derived.missing.iter() {
+            let label = basic_lable(&format!("{} is not in the open books", source));
+            label.add_css_class("warning");
+            details.append(&label);
+        }
+        for (source, err) in derived.errors.iter() {
+            details.append(&error_maker(&source.to_string(), err));
+        }
+        details.append(&stat_maker("Health", &derived.health));
+        details.append(&stat_maker("Armor Rating", &derived.armor_rating));
+        details.append(&stat_maker("Spell Level", &derived.spell_level));
+        details.append(&granted_maker("Skills", &derived.skills));
+        details.append(&granted_maker("Cybernetics", &derived.cybernetics));
+        details.append(&fields_maker(
+            "Choices",
+            character
+                .choices
+                .iter()
+                .map(|choice| {
+                    format!(
+                        "{}: {} ({} {})",
+                        choice.name, choice.picked, choice.class, choice.level
+                    )
+                    .into()
+                })
+                .collect(),
+        ));
+        details.append(&fields_maker(
+            "Built With",
+            file.books
+                .iter()
+                .map(|book| format!("{} {}", book.title, book.version).into())
+                .collect(),
+        ));
+
+        self.content
+            .append(&name_tag_content(&character.name, "Character", &details));
+        self.content.append(&Separator::new(Orientation::Vertical));
+    }
+
+    fn add_about(&self, book: &Book) {
+        self.add_anchor("About this book");
+
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+        match book.manifest() {
+            Some(manifest) => {
+                details.append(&markdown::render(manifest.description.localized()));
+                details.append(&fields_maker(
+                    "Version",
+                    vec![manifest.version.to_string().into()],
+                ));
+                details.append(&fields_maker("Authors", manifest.authors.clone()));
+                details.append(&fields_maker("License", vec![manifest.license.clone()]));
+                details.append(&fields_maker(
+                    "Game System",
+                    vec![manifest.system_version.to_string().into()],
+                ));
+            }
+            None => details.append(&basic_lable("This book has no manifest.")),
+        }
+
+        self.content
+            .append(&name_tag_content(book.title(), "Book", &details));
+        self.content.append(&Separator::new(Orientation::Vertical));
+    }
+
+    /// A row of buttons, one per name, each jumping to that sheet of `content_type`.
+    fn links_maker(&self, name: &str, content_type: ContentType, links: Vec<&str>) -> Box {
+        let buttons = Box::new(Orientation::Horizontal, 5);
+        for link in links {
+            let link_btn = Button::new();
+            link_btn.set_label(link);
+            link_btn.set_has_frame(false);
+
+            let key = (content_type, Some(link.into()));
+            let targets = Rc::clone(&self.targets);
+            let content_window_ref = self.content_window.clone();
+            link_btn.connect_clicked(move |_btn| {
+                if let Some(target) = targets.borrow().get(&key) {
+                    scroll_to(&content_window_ref, target);
+                }
+            });
+            buttons.append(&link_btn);
+        }
+
+        let boxed = Box::new(Orientation::Horizontal, 30);
+        boxed.append(&basic_lable(name));
+        boxed.append(&buttons);
+        boxed
+    }
+
+    /// Lists the book diagnostics, clicking one jumps to its sheet or, if the
+    /// sheet never loaded, to its section.
+    fn add_problems(&self, diagnostics: &[BookDiagnostic]) {
+        if diagnostics.is_empty() {
+            return;
+        }
+        self.add_anchor("Problems");
+
+        let problems = ListBox::new();
+        problems.set_selection_mode(gtk4::SelectionMode::None);
+        for diagnostic in diagnostics {
+            let label = basic_lable(&diagnostic.to_string());
+            label.set_selectable(false);
+            label.set_xalign(0.0);
+            match diagnostic.severity {
+                Severity::Error => label.add_css_class("error"),
+                Severity::Warning => label.add_css_class("warning"),
+                Severity::Info => {}
+            }
+
+            let problem_btn = Button::new();
+            problem_btn.set_child(Some(&label));
+            problem_btn.set_has_frame(false);
+
+            if let Some(content_type) = diagnostic.content_type {
+                let keys = [
+                    (content_type, diagnostic.sheet.clone()),
+                    (content_type, None),
+                ];
+                let targets = Rc::clone(&self.targets);
+                let content_window_ref = self.content_window.clone();
+                problem_btn.connect_clicked(move |_btn| {
+                    let targets = targets.borrow();
+                    if let Some(target) = keys.iter().find_map(|key| targets.get(key)) {
+                        scroll_to(&content_window_ref, target);
+                    }
+                });
+            }
+
+            problems.append(&problem_btn);
+        }
+        self.content.append(&problems);
+        self.content.append(&Separator::new(Orientation::Vertical));
+    }
+}
+
+pub(super) fn fields_maker(name: &str, details: Vec<std::boxed::Box<str>>) -> Box {
+    let boxed = Box::new(Orientation::Horizontal, 30);
+    boxed.append(&basic_lable(name));
+    boxed.append(&basic_lable(details.join(",  ").as_str()));
+    boxed
+}
+
+/// Every ability's score and modifier, with the saving throw bonus next to it.
+fn abilities_maker(character: &Character, book: &Book) -> Grid {
+    let grid = Grid::new();
+    grid.set_column_spacing(20);
+    grid.set_row_spacing(5);
+    for (column, heading) in ["Ability", "Score", "Modifier", "Saving Throw"]
+        .into_iter()
+        .enumerate()
+    {
+        grid.attach(&basic_lable(heading), column as i32, 0, 1, 1);
+    }
+    for (row, save) in character.saving_throws(book).into_iter().enumerate() {
+        let ability = character.abilities.get(save.ability);
+        let save_text = match save.proficient {
+            true => format!("{:+} (proficient)", save.bonus),
+            false => format!("{:+}", save.bonus),
+        };
+        let row = row as i32 + 1;
+        grid.attach(&basic_lable(save.ability.as_str()), 0, row, 1, 1);
+        grid.attach(&basic_lable(&ability.score().to_string()), 1, row, 1, 1);
+        grid.attach(&basic_lable(&ability.modifier_text()), 2, row, 1, 1);
+        grid.attach(&basic_lable(&save_text), 3, row, 1, 1);
+    }
+    grid
+}
+
+/// A derived number, hovering it shows what it was made of.
+fn stat_maker(name: &str, stat: &Stat) -> Box {
+    let boxed = fields_maker(name, vec![stat.total.to_string().into()]);
+    let breakdown: Vec<String> = stat
+        .parts
+        .iter()
+        .map(|(source, value)| format!("{}: {:+}", source, value))
+        .collect();
+    boxed.set_tooltip_text(Some(&breakdown.join("\n")));
+    boxed
+}
+
+/// Granted names, hovering them shows which source granted which.
+fn granted_maker(name: &str, granted: &Granted) -> Box {
+    let boxed = fields_maker(name, granted.names.clone());
+    let breakdown: Vec<String> = granted
+        .parts
+        .iter()
+        .map(|(source, names)| format!("{}: {}", source, names.join(", ")))
+        .collect();
+    boxed.set_tooltip_text(Some(&breakdown.join("\n")));
+    boxed
+}
+
+/// A row showing a sheet's Lua error in place of the value it broke.
+pub(super) fn error_maker(name: &str, err: &PageError) -> Box {
+    let label = basic_lable(&err.to_string());
+    label.add_css_class("error");
+    if !err.traceback.is_empty() {
+        label.set_tooltip_text(Some(&err.traceback));
+    }
+
+    let boxed = Box::new(Orientation::Horizontal, 30);
+    boxed.append(&basic_lable(name));
+    boxed.append(&label);
+    boxed
+}
+
+/// A row with the values a sheet gives, or its error in place of them.
+fn try_fields_maker(
+    name: &str,
+    values: Result<Option<Vec<std::boxed::Box<str>>>, PageError>,
+) -> Box {
+    match values {
+        Ok(values) => fields_maker(name, values.unwrap_or_default()),
+        Err(err) => error_maker(name, &err),
+    }
+}
+
+/// A row with the one value a sheet gives, its default when absent, or its error.
+fn try_value_maker<T: ToString + Default>(name: &str, value: Result<Option<T>, PageError>) -> Box {
+    match value {
+        Ok(value) => fields_maker(name, vec![value.unwrap_or_default().to_string().into()]),
+        Err(err) => error_maker(name, &err),
+    }
+}
+
+/// The rendered description of a sheet, or its error.
+fn description_maker(page: &impl Page) -> Box {
+    match page.try_description() {
+        Ok(description) => markdown::render(&description.unwrap_or_default()),
+        Err(err) => error_maker("Description", &err),
+    }
+}
+
+/// A row with a value for every class level it is defined at, or the first error.
+fn levels_maker<T: ToString>(
+    name: &str,
+    value: impl Fn(ClassLevel) -> Result<Option<T>, PageError>,
+) -> Box {
+    let mut values: Vec<std::boxed::Box<str>> = vec![];
+    for level in LEVELS {
+        match value(level) {
+            Ok(Some(value)) => values.push(format!("{}: {}", level, value.to_string()).into()),
+            Ok(None) => {}
+            Err(err) => return error_maker(name, &err),
+        }
+    }
+    fields_maker(name, values)
+}
+
+/// Health, Armor Rating and spell level rows shared by classes and balances.
+fn stats_maker(page: &impl Page) -> Box {
+    let details = Box::new(Orientation::Vertical, 30);
+    details.append(&levels_maker("Health", |level| page.try_health(level)));
+    details.append(&levels_maker("Armor Rating", |level| {
+        page.try_armor_rating(level)
+    }));
+    details.append(&levels_maker("Spell Level", |level| {
+        page.try_spell_level(level)
+    }));
+    details
+}
+
+/// Which book a sheet came from and which books' sheets it replaced.
+fn provenance_maker(page: &impl Page) -> Box {
+    let mut details: Vec<std::boxed::Box<str>> =
+        page.origin().into_iter().map(Into::into).collect();
+    if !page.overrides().is_empty() {
+        let overrides = page
+            .overrides()
+            .iter()
+            .map(|origin| origin.as_ref())
+            .collect::<Vec<_>>()
+            .join(", ");
+        details.push(format!("overrides {}", overrides).into());
+    }
+    fields_maker("From", details)
+}
+
+/// Cost and weight rows every item shares.
+fn item_maker(item: &impl Item) -> Box {
+    let details = Box::new(Orientation::Vertical, 30);
+    details.append(&try_value_maker("Cost", item.try_cost()));
+    details.append(&try_value_maker("Weight", item.try_weight()));
+    details
+}
+
+fn skill_details(skill: Option<SkillSheet>) -> Box {
+    let details = Box::new(Orientation::Vertical, 10);
+    details.set_margin_start(20);
+    let skill = match skill {
+        Some(skill) => skill,
+        None => {
+            details.append(&basic_lable("This book has no sheet for this skill."));
+            return details;
+        }
+    };
+
+    details.append(&description_maker(&skill));
+    details.append(&try_value_maker("Action Cost", skill.try_action_cost()));
+    details.append(&match skill.try_uses_per_rest() {
+        Ok(Some(uses)) => fields_maker("Uses per Rest", vec![uses.to_string().into()]),
+        Ok(None) => fields_maker("Uses per Rest", vec!["At will".into()]),
+        Err(err) => error_maker("Uses per Rest", &err),
+    });
+    details.append(&try_fields_maker(
+        "Prerequisites",
+        skill.try_prerequisites(),
+    ));
+    details.append(&try_fields_maker("Tags", skill.try_tags()));
+    details
+}
+
+/// Every skill a class gains with the level it comes at, each expanding into its rules text.
+fn skills_maker(book: &Book, class: &impl Page) -> Box {
+    let skills = Box::new(Orientation::Vertical, 5);
+    let mut seen: Vec<std::boxed::Box<str>> = vec![];
+    for level in LEVELS {
+        let names = match class.try_skills(level) {
+            Ok(names) => names.unwrap_or_default(),
+            Err(err) => {
+                skills.append(&error_maker("", &err));
+                break;
+            }
+        };
+        for name in names {
+            if seen.contains(&name) {
+                continue;
+            }
+            let expander = Expander::new(Some(&format!("{} (level {})", name, level)));
+            expander.set_child(Some(&skill_details(book.skills.find(&name))));
+            skills.append(&expander);
+            seen.push(name);
+        }
+    }
+
+    let boxed = Box::new(Orientation::Horizontal, 30);
+    boxed.append(&basic_lable("Skills"));
+    boxed.append(&skills);
+    boxed
+}
+
+fn render(helper: &Helper, shelf: &Shelf) {
+    helper.window.set_title(Some(&shelf.title()));
+
+    let book = shelf.merged();
+    let book = &*book;
+
+    helper.add_character(shelf, book);
+    for book in shelf.books() {
+        helper.add_about(book);
+    }
+
+    let mut diagnostics = book.diagnostics();
+    diagnostics.extend(helper.watch_problems.borrow().iter().cloned());
+    diagnostics.extend(lint::lint(book));
+    helper.add_problems(&diagnostics);
+
+    helper.add_section(ContentType::Race);
+    for race in book.race.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&race));
+        details.append(&try_value_maker("Speed", race.try_speed()));
+        details.append(&try_value_maker("Size", race.try_size()));
+        details.append(&try_value_maker("Age", race.try_age()));
+        details.append(&try_fields_maker("Languages", race.try_languages()));
+        details.append(&provenance_maker(&race));
+
+        helper.add_target(ContentType::Race, race.name());
+        helper
+            .content
+            .append(&name_tag_content(race.name(), "Race", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Class);
+    for class in book.class.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&class));
+        details.append(&try_fields_maker(
+            "Astralic Types",
+            class.try_astralic_types(),
+        ));
+        details.append(&try_fields_maker(
+            "Saving Throws",
+            class.try_saving_throws(),
+        ));
+        details.append(&skills_maker(book, &class));
+        details.append(&stats_maker(&class));
+        details.append(&provenance_maker(&class));
+
+        helper.add_target(ContentType::Class, class.name());
+        helper
+            .content
+            .append(&name_tag_content(class.name(), "Class", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Skill);
+    for skill in book.skills.iter() {
+        let details = skill_details(Some(skill.clone()));
+        details.set_hexpand(true);
+        details.append(&provenance_maker(&skill));
+
+        helper.add_target(ContentType::Skill, skill.name());
+        helper
+            .content
+            .append(&name_tag_content(skill.name(), "Skill", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Spell);
+    for spell in book.spells.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&spell));
+        details.append(&try_value_maker("Level", spell.try_level()));
+        details.append(&try_value_maker("Astralic Type", spell.try_astralic_type()));
+        details.append(&try_value_maker("Action Points", spell.try_action_points()));
+        details.append(&try_value_maker("Range", spell.try_range()));
+        details.append(&try_value_maker("Duration", spell.try_duration()));
+        details.append(&provenance_maker(&spell));
+
+        helper.add_target(ContentType::Spell, spell.name());
+        helper
+            .content
+            .append(&name_tag_content(spell.name(), "Spell", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Astralic);
+    for astralic in book.astralics.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&astralic));
+        details.append(&try_value_maker(
+            "Casting Ability",
+            astralic.try_casting_ability(),
+        ));
+        details.append(&try_fields_maker("Opposed", astralic.try_opposed()));
+        details.append(&try_fields_maker("Synergistic", astralic.try_synergistic()));
+        let classes = book.classes_using(astralic.name());
+        details.append(&helper.links_maker(
+            "Classes",
+            ContentType::Class,
+            classes.iter().map(|class| class.name()).collect(),
+        ));
+        details.append(&provenance_maker(&astralic));
+
+        helper.add_target(ContentType::Astralic, astralic.name());
+        helper
+            .content
+            .append(&name_tag_content(astralic.name(), "Astralic", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Weapon);
+    for weapon in book.weapons.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&weapon));
+        details.append(&try_value_maker("Damage", weapon.try_damage()));
+        details.append(&try_fields_maker("Properties", weapon.try_properties()));
+        details.append(&try_value_maker("Mastery", weapon.try_mastery()));
+        details.append(&item_maker(&weapon));
+        details.append(&provenance_maker(&weapon));
+
+        helper.add_target(ContentType::Weapon, weapon.name());
+        helper
+            .content
+            .append(&name_tag_content(weapon.name(), "Weapon", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Armor);
+    for armor in book.armor.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&armor));
+        details.append(&match armor.try_armor_bonus() {
+            Ok(bonus) => fields_maker(
+                "Armor Rating",
+                vec![format!("+{}", bonus.unwrap_or_default()).into()],
+            ),
+            Err(err) => error_maker("Armor Rating", &err),
+        });
+        details.append(&try_fields_maker("Requirements", armor.try_requirements()));
+        details.append(&item_maker(&armor));
+        details.append(&provenance_maker(&armor));
+
+        helper.add_target(ContentType::Armor, armor.name());
+        helper
+            .content
+            .append(&name_tag_content(armor.name(), "Armor", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Gear);
+    for gear in book.gear.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&gear));
+        details.append(&item_maker(&gear));
+        details.append(&provenance_maker(&gear));
+
+        helper.add_target(ContentType::Gear, gear.name());
+        helper
+            .content
+            .append(&name_tag_content(gear.name(), "Gear", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Balance);
+    for balance in book.balance.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&balance));
+        details.append(&skills_maker(book, &balance));
+        details.append(&stats_maker(&balance));
+        details.append(&provenance_maker(&balance));
+
+        helper.add_target(ContentType::Balance, balance.name());
+        helper
+            .content
+            .append(&name_tag_content(balance.name(), "Balance", &details));
+
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
+    }
+
+    helper.add_section(ContentType::Cybernetic);
+    for cybernetic in book.cybernetics.iter() {
+        let details = Box::new(Orientation::Vertical, 30);
+        details.set_hexpand(true);
+
+        details.append(&description_maker(&cybernetic));
+        details.append(&try_value_maker("Body Part", cybernetic.try_body_part()));
+        details.append(&try_value_maker("Cost", cybernetic.try_cost()));
+        details.append(&try_fields_maker("Effects", cybernetic.try_effects()));
+        details.append(&provenance_maker(&cybernetic));
+
+        helper.add_target(ContentType::Cybernetic, cybernetic.name());
+        helper
+            .content
+            .append(&name_tag_content(cybernetic.name(), "Cybernetic", &details));
+        helper
+            .content
+            .append(&Separator::new(Orientation::Vertical));
     }
 }
 
-pub fn full(book: Rc<Book>) -> Option<()> {
+pub fn full(shelf: Rc<RefCell<Shelf>>) -> Option<()> {
     let result: Rc<RefCell<Option<()>>> = Rc::new(RefCell::new(None));
     //let result_ref = Rc::clone(&result);
     let app = Application::builder().application_id(APP_ID).build();
@@ -71,47 +740,112 @@ pub fn full(book: Rc<Book>) -> Option<()> {
         content_window.set_child(Some(&content));
 
         let helper = Helper {
-            content_window: &content_window,
-            content: &content,
-            sidebar: &sidebar,
+            window: window.clone(),
+            content_window: content_window.clone(),
+            content: content.clone(),
+            sidebar: sidebar.clone(),
+            targets: Rc::new(RefCell::new(HashMap::new())),
+            character: Rc::new(RefCell::new(None)),
+            watch_problems: Rc::new(RefCell::new(vec![])),
         };
 
-        helper.add_anchor("Class");
-        for class in book.class.iter() {
-            let fields_maker = |name, details: Vec<std::boxed::Box<str>>| {
-                let boxed = Box::new(Orientation::Horizontal, 30);
-                boxed.append(&basic_lable(name));
-                boxed.append(&basic_lable(details.join(",  ").as_str()));
-                boxed
-            };
-
-            let details = Box::new(Orientation::Vertical, 30);
-            details.set_hexpand(true);
+        let header = HeaderBar::new();
+        window.set_titlebar(Some(&header));
 
-            details.append(&basic_lable(&class.description()));
-            details.append(&fields_maker(
-                "Astralic Types".into(),
-                class.astralic_types(),
-            ));
-            details.append(&fields_maker("Saving Throws".into(), class.saving_throws()));
-            details.append(&fields_maker("Skills".into(), class.skills(0)));
+        let save_btn = Button::with_label("Save Character");
+        save_btn.set_sensitive(false);
+        let shelf_ref = Rc::clone(&shelf);
+        let helper_ref = helper.clone();
+        save_btn.connect_clicked(move |_btn| {
+            let mut character = helper_ref.character.borrow_mut();
+            let file = match character.as_mut() {
+                Some(file) => file,
+                None => return,
+            };
+            if let Some(path) = dialog::save_character(&file.character.name) {
+                if let Err(err) = file.save(&path, &shelf_ref.borrow()) {
+                    dialog::error(err);
+                }
+            }
+        });
 
-            content.append(&name_tag_content(class.name(), "Class", &details));
-            content.append(&Separator::new(Orientation::Vertical));
-        }
+        let level_btn = Button::with_label("Level Up");
+        level_btn.set_sensitive(false);
+        let shelf_ref = Rc::clone(&shelf);
+        let helper_ref = helper.clone();
+        level_btn.connect_clicked(move |_btn| {
+            let character = match helper_ref.character.borrow().as_ref() {
+                Some(file) => file.character.clone(),
+                None => return,
+            };
+            let book = shelf_ref.borrow().merged();
+            let shelf_ref = Rc::clone(&shelf_ref);
+            let helper_ref = helper_ref.clone();
+            level_up::wizard(
+                &helper_ref.window.clone(),
+                book,
+                character,
+                move |character| {
+                    if let Some(file) = helper_ref.character.borrow_mut().as_mut() {
+                        file.character = character;
+                        if let Some(path) = dialog::save_character(&file.character.name) {
+                            if let Err(err) = file.save(&path, &shelf_ref.borrow()) {
+                                dialog::error(err);
+                            }
+                        }
+                    }
+                    helper_ref.clear();
+                    render(&helper_ref, &shelf_ref.borrow());
+                },
+            );
+        });
 
-        helper.add_anchor("Balance");
-        for balance in book.balance.iter() {
-            let details = basic_lable(&balance.description());
-            details.set_hexpand(true);
+        let open_btn = Button::with_label("Open Character");
+        let shelf_ref = Rc::clone(&shelf);
+        let helper_ref = helper.clone();
+        let save_btn_ref = save_btn.clone();
+        let level_btn_ref = level_btn.clone();
+        open_btn.connect_clicked(move |_btn| {
+            let path = match dialog::open_character() {
+                Some(path) => path,
+                None => return,
+            };
+            match CharacterFile::open(&path) {
+                Ok(file) => {
+                    *helper_ref.character.borrow_mut() = Some(file);
+                    save_btn_ref.set_sensitive(true);
+                    level_btn_ref.set_sensitive(true);
+                    helper_ref.clear();
+                    render(&helper_ref, &shelf_ref.borrow());
+                }
+                Err(err) => dialog::error(err),
+            }
+        });
+        header.pack_start(&open_btn);
+        header.pack_start(&save_btn);
+        header.pack_start(&level_btn);
 
-            content.append(&name_tag_content(balance.name(), "Balance", &details));
+        render(&helper, &shelf.borrow());
 
-            content.append(&Separator::new(Orientation::Vertical));
+        let directories = shelf.borrow().directories();
+        for directory in directories {
+            let shelf_ref = Rc::clone(&shelf);
+            let helper_ref = helper.clone();
+            let on_change = move |path: &std::path::Path| {
+                shelf_ref.borrow_mut().reload_file(path);
+                helper_ref.clear();
+                render(&helper_ref, &shelf_ref.borrow());
+            };
+            let shelf_ref = Rc::clone(&shelf);
+            let helper_ref = helper.clone();
+            let on_error = move |diagnostic| {
+                helper_ref.watch_problems.borrow_mut().push(diagnostic);
+                helper_ref.clear();
+                render(&helper_ref, &shelf_ref.borrow());
+            };
+            watch::lua_files(&directory, on_change, on_error);
         }
 
-        helper.add_anchor("Cybernetic");
-
         window.show();
     });
     let mock_args: [String; 0] = [];
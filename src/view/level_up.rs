@@ -0,0 +1,257 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gtk4::{
+    prelude::*, Box, Button, DropDown, ListBox, Orientation, ScrolledWindow, Stack, Window,
+};
+
+use crate::sheet::{
+    book::Book,
+    character::Character,
+    derive::Stat,
+    level_up::{Changes, LevelUp, Offer},
+};
+
+use super::{
+    basic_lable,
+    book::{error_maker, fields_maker},
+};
+
+const CLASS_STEP: &str = "class";
+const CHANGES_STEP: &str = "changes";
+const CHOICES_STEP: &str = "choices";
+
+/// What the wizard knows once a level has been picked.
+struct Picked {
+    level_up: LevelUp,
+    changes: Changes,
+    /// A drop down for every offer, in the order of `changes.offers`.
+    dropdowns: Vec<DropDown>,
+}
+
+/// A row showing a derived number before and after the level, with the difference.
+fn stat_change_maker(name: &str, before: &Stat, after: &Stat) -> Box {
+    let difference = after.total - before.total;
+    let text = match difference {
+        0 => before.total.to_string(),
+        _ => format!("{} → {} ({:+})", before.total, after.total, difference),
+    };
+    fields_maker(name, vec![text.into()])
+}
+
+fn changes_maker(changes: &Changes) -> Box {
+    let details = Box::new(Orientation::Vertical, 20);
+    for violation in changes.violations.iter() {
+        let label = basic_lable(&violation.to_string());
+        label.add_css_class("error");
+        details.append(&label);
+    }
+    for (source, err) in changes.after.errors.iter().chain(changes.errors.iter()) {
+        details.append(&error_maker(&source.to_string(), err));
+    }
+    details.append(&stat_change_maker(
+        "Health",
+        &changes.before.health,
+        &changes.after.health,
+    ));
+    details.append(&stat_change_maker(
+        "Armor Rating",
+        &changes.before.armor_rating,
+        &changes.after.armor_rating,
+    ));
+    details.append(&stat_change_maker(
+        "Spell Level",
+        &changes.before.spell_level,
+        &changes.after.spell_level,
+    ));
+    details.append(&fields_maker("New Skills", changes.skills.clone()));
+    details.append(&fields_maker(
+        "New Cybernetics",
+        changes.cybernetics.clone(),
+    ));
+    let offers = changes
+        .offers
+        .iter()
+        .map(|offer| offer.name.clone())
+        .collect();
+    details.append(&fields_maker("Choices", offers));
+    details
+}
+
+fn offer_dropdown(offer: &Offer) -> DropDown {
+    let options: Vec<&str> = offer.options.iter().map(|option| option.as_ref()).collect();
+    DropDown::from_strings(&options)
+}
+
+/// Swaps everything in `g_box` for `child`.
+fn replace_child(g_box: &Box, child: &impl IsA<gtk4::Widget>) {
+    while let Some(old) = g_box.first_child() {
+        g_box.remove(&old);
+    }
+    g_box.append(child);
+}
+
+/// Walks the player through taking a level: which class it goes to, what that changes
+/// and the choices it offers. `done` gets the levelled character, nothing happens if
+/// the wizard is closed before the end.
+pub fn wizard(
+    parent: &impl IsA<Window>,
+    book: Rc<Book>,
+    character: Character,
+    done: impl Fn(Character) + 'static,
+) {
+    let window = Window::builder()
+        .title(format!("Level Up {}", character.name))
+        .transient_for(parent)
+        .modal(true)
+        .default_width(480)
+        .default_height(480)
+        .build();
+
+    let options = LevelUp::options(&character, &book);
+    let class_list = ListBox::new();
+    for option in options.iter() {
+        let label = basic_lable(&option.to_string());
+        label.set_selectable(false);
+        label.set_xalign(0.0);
+        class_list.append(&label);
+    }
+    class_list.select_row(class_list.row_at_index(0).as_ref());
+
+    let changes_page = Box::new(Orientation::Vertical, 20);
+    let choices_page = Box::new(Orientation::Vertical, 20);
+
+    let stack = Stack::new();
+    stack.set_vexpand(true);
+    for (name, page) in [
+        (CLASS_STEP, class_list.upcast_ref::<gtk4::Widget>()),
+        (CHANGES_STEP, changes_page.upcast_ref()),
+        (CHOICES_STEP, choices_page.upcast_ref()),
+    ] {
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_child(Some(page));
+        stack.add_named(&scrolled, Some(name));
+    }
+
+    let back_btn = Button::with_label("Back");
+    let next_btn = Button::with_label("Next");
+    let buttons = Box::new(Orientation::Horizontal, 10);
+    buttons.set_halign(gtk4::Align::End);
+    buttons.append(&back_btn);
+    buttons.append(&next_btn);
+
+    let content = Box::new(Orientation::Vertical, 10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.append(&stack);
+    content.append(&buttons);
+    window.set_child(Some(&content));
+
+    let picked: Rc<RefCell<Option<Picked>>> = Rc::new(RefCell::new(None));
+
+    // Labels and enables the buttons for the step being shown.
+    let show = {
+        let stack = stack.clone();
+        let back_btn = back_btn.clone();
+        let next_btn = next_btn.clone();
+        let picked = Rc::clone(&picked);
+        move |step: &str| {
+            stack.set_visible_child_name(step);
+            back_btn.set_sensitive(step != CLASS_STEP);
+            let picked = picked.borrow();
+            let finishes = match (step, picked.as_ref()) {
+                (CHANGES_STEP, Some(picked)) => picked.changes.offers.is_empty(),
+                (CHOICES_STEP, _) => true,
+                _ => false,
+            };
+            next_btn.set_label(if finishes { "Level Up" } else { "Next" });
+            let allowed = match (step, picked.as_ref()) {
+                (CLASS_STEP, _) => true,
+                (_, Some(picked)) => picked.changes.violations.is_empty(),
+                (_, None) => false,
+            };
+            next_btn.set_sensitive(allowed);
+        }
+    };
+    show(CLASS_STEP);
+
+    let stack_ref = stack.clone();
+    let show_ref = show.clone();
+    back_btn.connect_clicked(
+        move |_btn| match stack_ref.visible_child_name().as_deref() {
+            Some(CHOICES_STEP) => show_ref(CHANGES_STEP),
+            _ => show_ref(CLASS_STEP),
+        },
+    );
+
+    let window_ref = window.clone();
+    next_btn.connect_clicked(move |_btn| {
+        let step = stack.visible_child_name();
+        let finishing = match step.as_deref() {
+            Some(CLASS_STEP) => {
+                let option = match class_list
+                    .selected_row()
+                    .and_then(|row| options.get(row.index() as usize))
+                {
+                    Some(option) => option.clone(),
+                    None => return,
+                };
+                let changes = option.changes(&character, &book);
+                replace_child(&changes_page, &changes_maker(&changes));
+
+                let choices = Box::new(Orientation::Vertical, 20);
+                let dropdowns: Vec<DropDown> = changes.offers.iter().map(offer_dropdown).collect();
+                for (offer, dropdown) in changes.offers.iter().zip(dropdowns.iter()) {
+                    let row = Box::new(Orientation::Horizontal, 30);
+                    row.append(&basic_lable(&format!("{} ({})", offer.name, offer.source)));
+                    row.append(dropdown);
+                    choices.append(&row);
+                }
+                replace_child(&choices_page, &choices);
+
+                *picked.borrow_mut() = Some(Picked {
+                    level_up: option,
+                    changes,
+                    dropdowns,
+                });
+                show(CHANGES_STEP);
+                false
+            }
+            Some(CHANGES_STEP) => {
+                let has_offers = picked
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|picked| !picked.changes.offers.is_empty());
+                if has_offers {
+                    show(CHOICES_STEP);
+                }
+                !has_offers
+            }
+            _ => true,
+        };
+        if !finishing {
+            return;
+        }
+
+        let picked = picked.borrow();
+        let picked = match picked.as_ref() {
+            Some(picked) => picked,
+            None => return,
+        };
+        let picks = picked
+            .changes
+            .offers
+            .iter()
+            .zip(picked.dropdowns.iter())
+            .filter_map(|(offer, dropdown)| {
+                let option = offer.options.get(dropdown.selected() as usize)?;
+                Some(picked.level_up.pick(offer, option))
+            })
+            .collect();
+        done(picked.level_up.apply(&character, picks));
+        window_ref.close();
+    });
+
+    window.present();
+}
@@ -0,0 +1,354 @@
+use gtk4::{glib, prelude::*, Box as GBox, Grid, Label, Orientation};
+
+use super::basic_lable;
+
+/// A run of text inside a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    /// `*text*` or `_text_`
+    Emphasis(Vec<Inline>),
+    /// `**text**` or `__text__`
+    Strong(Vec<Inline>),
+    /// `` `text` ``
+    Code(String),
+}
+
+/// The Markdown sheet descriptions are written in: headings, paragraphs, bullet lists
+/// and simple tables. Anything else shows as plain text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// `#` to `######` with how many there were.
+    Heading(usize, Vec<Inline>),
+    /// Lines up to the next blank line, heading, list or table.
+    Paragraph(Vec<Inline>),
+    /// Items starting with `- `, `* ` or `+ `, each with how deeply it is indented.
+    Bullets(Vec<(usize, Vec<Inline>)>),
+    /// Rows of `|` separated cells, with a `|---|---|` line below the header.
+    Table {
+        header: Vec<Vec<Inline>>,
+        rows: Vec<Vec<Vec<Inline>>>,
+    },
+}
+
+/// Spaces of indentation that make a bullet one level deeper.
+const INDENT: usize = 2;
+
+pub fn parse(text: &str) -> Vec<Block> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut blocks = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(inlines(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let trimmed = line.trim();
+        index += 1;
+
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if let Some((level, heading)) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading(level, inlines(heading)));
+        } else if let Some(item) = bullet(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            let depth = (line.len() - line.trim_start().len()) / INDENT;
+            match blocks.last_mut() {
+                Some(Block::Bullets(items)) => items.push((depth, inlines(item))),
+                _ => blocks.push(Block::Bullets(vec![(depth, inlines(item))])),
+            }
+        } else if trimmed.starts_with('|')
+            && lines
+                .get(index)
+                .is_some_and(|next| is_separator(&cells(next)))
+        {
+            flush(&mut paragraph, &mut blocks);
+            let header = cells(trimmed).into_iter().map(inlines).collect();
+            let mut rows = vec![];
+            index += 1;
+            while let Some(row) = lines.get(index).map(|row| row.trim()) {
+                if !row.starts_with('|') {
+                    break;
+                }
+                rows.push(cells(row).into_iter().map(inlines).collect());
+                index += 1;
+            }
+            blocks.push(Block::Table { header, rows });
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| (level, text.trim()))
+}
+
+fn bullet(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .map(str::trim)
+}
+
+fn cells(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(str::trim).collect()
+}
+
+/// Whether the cells are the `---`, `:--` or `:-:` line under a table's header.
+fn is_separator(cells: &[&str]) -> bool {
+    cells.iter().all(|cell| {
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    })
+}
+
+fn inlines(text: &str) -> Vec<Inline> {
+    let mut inlines = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        // `_` only emphasises at the start of a word, so snake_case stays as it is
+        let opens = c != '_' || !plain.chars().last().is_some_and(char::is_alphanumeric);
+        let found = match c {
+            '`' => delimited(rest, "`").map(|(code, after)| (Inline::Code(code.into()), after)),
+            '*' | '_' if opens => {
+                let double = if c == '*' { "**" } else { "__" };
+                if rest.starts_with(double) {
+                    delimited(rest, double)
+                        .map(|(inner, after)| (Inline::Strong(self::inlines(inner)), after))
+                } else {
+                    delimited(rest, &double[..1])
+                        .map(|(inner, after)| (Inline::Emphasis(self::inlines(inner)), after))
+                }
+            }
+            _ => None,
+        };
+        match found {
+            Some((inline, after)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                inlines.push(inline);
+                rest = after;
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+    inlines
+}
+
+/// Splits `text`, which starts with `marker`, into what lies between it and the closing
+/// `marker` and what follows. A single `*` or `_` skips doubled ones while looking.
+fn delimited<'t>(text: &'t str, marker: &str) -> Option<(&'t str, &'t str)> {
+    let inner = &text[marker.len()..];
+    if inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut from = 0;
+    while let Some(found) = inner[from..].find(marker) {
+        let end = from + found;
+        let doubled = marker.len() == 1 && inner[end + 1..].starts_with(marker);
+        if doubled {
+            from = end + 2;
+            continue;
+        }
+        if end == 0 {
+            return None;
+        }
+        return Some((&inner[..end], &inner[end + marker.len()..]));
+    }
+    None
+}
+
+/// Pango markup for a run of inlines.
+fn markup(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => glib::markup_escape_text(text).to_string(),
+            Inline::Emphasis(inner) => format!("<i>{}</i>", markup(inner)),
+            Inline::Strong(inner) => format!("<b>{}</b>", markup(inner)),
+            Inline::Code(code) => format!("<tt>{}</tt>", glib::markup_escape_text(code)),
+        })
+        .collect()
+}
+
+fn markup_lable(markup: &str) -> Label {
+    let label = basic_lable("");
+    label.set_markup(markup);
+    label.set_xalign(0.0);
+    label
+}
+
+/// Lays the Markdown in `text` out as GTK widgets.
+pub fn render(text: &str) -> GBox {
+    let g_box = GBox::new(Orientation::Vertical, 10);
+    for block in parse(text) {
+        match block {
+            Block::Heading(level, heading) => {
+                let size = match level {
+                    1 => "xx-large",
+                    2 => "x-large",
+                    3 => "large",
+                    _ => "medium",
+                };
+                g_box.append(&markup_lable(&format!(
+                    "<span size=\"{}\" weight=\"bold\">{}</span>",
+                    size,
+                    markup(&heading)
+                )));
+            }
+            Block::Paragraph(paragraph) => g_box.append(&markup_lable(&markup(&paragraph))),
+            Block::Bullets(items) => {
+                let list = GBox::new(Orientation::Vertical, 5);
+                for (depth, item) in items {
+                    let row = GBox::new(Orientation::Horizontal, 8);
+                    row.set_margin_start(depth as i32 * 20);
+                    row.append(&markup_lable("•"));
+                    let label = markup_lable(&markup(&item));
+                    label.set_hexpand(true);
+                    row.append(&label);
+                    list.append(&row);
+                }
+                g_box.append(&list);
+            }
+            Block::Table { header, rows } => {
+                let grid = Grid::new();
+                grid.set_column_spacing(20);
+                grid.set_row_spacing(5);
+                for (column, cell) in header.iter().enumerate() {
+                    let label = markup_lable(&format!("<b>{}</b>", markup(cell)));
+                    grid.attach(&label, column as i32, 0, 1, 1);
+                }
+                for (row, cells) in rows.iter().enumerate() {
+                    for (column, cell) in cells.iter().enumerate() {
+                        let label = markup_lable(&markup(cell));
+                        grid.attach(&label, column as i32, row as i32 + 1, 1, 1);
+                    }
+                }
+                g_box.append(&grid);
+            }
+        }
+    }
+    g_box
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Inline {
+        Inline::Text(text.into())
+    }
+
+    #[test]
+    fn inlines_find_emphasis_strong_and_code() {
+        for (source, expected) in [
+            ("plain words", vec![text("plain words")]),
+            (
+                "a *light* touch",
+                vec![
+                    text("a "),
+                    Inline::Emphasis(vec![text("light")]),
+                    text(" touch"),
+                ],
+            ),
+            (
+                "**+10** to _Armor Rating_",
+                vec![
+                    Inline::Strong(vec![text("+10")]),
+                    text(" to "),
+                    Inline::Emphasis(vec![text("Armor Rating")]),
+                ],
+            ),
+            (
+                "*a **bold** move*",
+                vec![Inline::Emphasis(vec![
+                    text("a "),
+                    Inline::Strong(vec![text("bold")]),
+                    text(" move"),
+                ])],
+            ),
+            (
+                "call `book.get`",
+                vec![text("call "), Inline::Code("book.get".into())],
+            ),
+            (
+                "2 * 3 * 4 and class_level_one",
+                vec![text("2 * 3 * 4 and class_level_one")],
+            ),
+            ("an *unclosed star", vec![text("an *unclosed star")]),
+        ] {
+            assert_eq!(inlines(source), expected, "Failed for: {}", source);
+        }
+    }
+
+    #[test]
+    fn parse_splits_headings_paragraphs_lists_and_tables() {
+        let source = "\
+# Full Caster
+You cast spells
+at the *fastest* pace.
+- Level 1 = 1st level spells
+  - more slots
+- Level 3 = 2nd level spells
+
+| Level | Spell Level |
+|------:|:-----------:|
+| 1 | 1st |
+| 3 | 2nd |
+| not a | table |
+";
+        assert_eq!(
+            parse(source),
+            vec![
+                Block::Heading(1, vec![text("Full Caster")]),
+                Block::Paragraph(vec![
+                    text("You cast spells at the "),
+                    Inline::Emphasis(vec![text("fastest")]),
+                    text(" pace."),
+                ]),
+                Block::Bullets(vec![
+                    (0, vec![text("Level 1 = 1st level spells")]),
+                    (1, vec![text("more slots")]),
+                    (0, vec![text("Level 3 = 2nd level spells")]),
+                ]),
+                Block::Table {
+                    header: vec![vec![text("Level")], vec![text("Spell Level")]],
+                    rows: vec![
+                        vec![vec![text("1")], vec![text("1st")]],
+                        vec![vec![text("3")], vec![text("2nd")]],
+                        vec![vec![text("not a")], vec![text("table")]],
+                    ],
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse("| no separator |\n#not a heading"),
+            vec![Block::Paragraph(vec![text(
+                "| no separator | #not a heading"
+            )])]
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use gtk4::{prelude::*, Align, Box as GBox, Label, Orientation, Separator, Widget};
 
 pub mod book;
+mod level_up;
+mod markdown;
+mod watch;
 
 pub const APP_ID: &str = "org.computingfun.cybernetic-trpg";
 
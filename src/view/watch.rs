@@ -0,0 +1,75 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use gtk4::{
+    gio::{self, FileMonitor, FileMonitorEvent, FileMonitorFlags},
+    prelude::*,
+};
+
+use crate::sheet::diagnostic::BookDiagnostic;
+
+type OnChange = Rc<dyn Fn(&Path)>;
+type OnError = Rc<dyn Fn(BookDiagnostic)>;
+type Monitors = Rc<RefCell<Vec<FileMonitor>>>;
+
+/// Calls `on_change` with the path of every `.lua` file saved, created or deleted
+/// in `directory` or any folder below it, and `on_error` for every folder that can't
+/// be watched. The monitors live as long as the application.
+pub fn lua_files(
+    directory: &Path,
+    on_change: impl Fn(&Path) + 'static,
+    on_error: impl Fn(BookDiagnostic) + 'static,
+) {
+    let monitors: Monitors = Rc::new(RefCell::new(vec![]));
+    watch(directory, Rc::new(on_change), Rc::new(on_error), &monitors);
+}
+
+fn watch(directory: &Path, on_change: OnChange, on_error: OnError, monitors: &Monitors) {
+    let monitor = match gio::File::for_path(directory)
+        .monitor_directory(FileMonitorFlags::NONE, gio::Cancellable::NONE)
+    {
+        Ok(monitor) => monitor,
+        Err(err) => {
+            on_error(BookDiagnostic::warning(
+                directory.to_string_lossy(),
+                format!("changes to the folder won't be picked up: {}", err),
+            ));
+            return;
+        }
+    };
+
+    let on_change_ref = Rc::clone(&on_change);
+    let on_error_ref = Rc::clone(&on_error);
+    let monitors_ref = Rc::clone(monitors);
+    monitor.connect_changed(move |_monitor, file, _other_file, event| {
+        let path = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+        match event {
+            FileMonitorEvent::Created if path.is_dir() => watch(
+                &path,
+                Rc::clone(&on_change_ref),
+                Rc::clone(&on_error_ref),
+                &monitors_ref,
+            ),
+            FileMonitorEvent::ChangesDoneHint
+            | FileMonitorEvent::Created
+            | FileMonitorEvent::Deleted
+                if path.extension().and_then(|osstr| osstr.to_str()) == Some("lua") =>
+            {
+                on_change_ref(&path);
+            }
+            _ => {}
+        }
+    });
+    monitors.borrow_mut().push(monitor);
+
+    if let Ok(entries) = std::fs::read_dir(directory) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                watch(&path, Rc::clone(&on_change), Rc::clone(&on_error), monitors);
+            }
+        }
+    }
+}